@@ -1,64 +1,330 @@
-use clap::{arg, command};
+use crate::color::Rgb;
+use crate::contrast::ContrastThresholds;
+use crate::cvd::Deficiency;
+use crate::theme_creator::OUTPUT_FORMATS;
+use crate::theme_parser::detect_theme_format;
+use crate::transform::ColorTransform;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{arg, command, value_parser, Arg, ArgAction, ArgGroup, Command};
+use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 
 #[derive(Debug)]
 pub struct Options {
+    pub command: String,
+    pub script: bool,
+    pub reset: bool,
+    pub apca: bool,
+    pub contrast_thresholds: ContrastThresholds,
+    pub fix_contrast: Option<f64>,
+    pub opposite_variant: bool,
+    pub generate_brights: String,
+    pub bright_strength: f64,
+    pub transforms: Vec<ColorTransform>,
+    pub transform_roles: Vec<String>,
+    pub simulate: Option<Deficiency>,
+    pub daltonize: Option<Deficiency>,
+    pub cvd: bool,
+    pub min_cvd_distance: f64,
+    pub generate_accent: Option<Rgb>,
+    pub generate_image: Option<String>,
+    pub generate_dark: Option<bool>,
     pub term_input: String,
     pub term_input_file: String,
-    pub foot_output_folder: String,
-    pub kitty_output_folder: String,
-    pub alacritty_output_folder: String,
-    pub terminal_output: String,
+    pub output_folders: HashMap<String, String>,
+    pub vt_kernel_cmdline: bool,
+    pub terminal_outputs: Vec<String>,
+    pub skip_input_format: bool,
     pub theme_name: String,
+    pub explicit_theme_name: bool,
+}
+
+// the input arguments shared by the conversion and every subcommand
+fn input_args() -> [Arg; 2] {
+    [
+        arg!(-i --"term-input" <"kitty,alacritty,xresources,gnome-terminal,konsole,base16,base24,vscode,gogh,neovim,vim,pywal,terminal,auto"> "Terminal input name, guessed from the theme file by default")
+            .default_value("auto"),
+        // required as well, checked in parse_args since the terminal input defaults it
+        arg!(-f --"term-input-file" <term_input_file> "The theme file for the terminal input (the tty to query for terminal), or a folder or quoted glob of themes to convert them all")
+            .default_value_if("term-input", "terminal", "/dev/tty"),
+    ]
+}
+
+fn simulate_arg() -> Arg {
+    arg!(--simulate <deficiency> "Show the colors as seen with a color vision deficiency")
+        .value_parser(["protanopia", "deuteranopia", "tritanopia"])
+}
+
+// the output arguments shared by the conversion and generate
+fn output_args() -> Vec<Arg> {
+    vec![
+        arg!(-n --"theme-name" <theme_name> "The theme name, instead of the one in the theme file")
+            .default_value("Theme ported with TTParser."),
+        arg!(--"foot-output-folder" <foot_output_folder> "The output folder for foot [default: $HOME/.config/foot]"),
+        arg!(--"alacritty-output-folder" <alacritty_output_folder> "The output folder for alacritty [default: $HOME/.config/alacritty]"),
+        arg!(--"kitty-output-folder" <kitty_output_folder> "The output folder for kitty [default: $HOME/.config/kitty]"),
+        arg!(--"xresources-output-folder" <xresources_output_folder> "The output folder for xresources [default: $HOME/.Xresources.d]"),
+        arg!(--"gnome-terminal-output-folder" <gnome_terminal_output_folder> "The output folder for gnome-terminal dconf dumps [default: $HOME/.config/ttparser/gnome-terminal]"),
+        arg!(--"konsole-output-folder" <konsole_output_folder> "The output folder for konsole [default: $HOME/.local/share/konsole]"),
+        arg!(--"base16-output-folder" <base16_output_folder> "The output folder for base16 schemes [default: $HOME/.config/ttparser/base16]"),
+        arg!(--"base24-output-folder" <base24_output_folder> "The output folder for base24 schemes [default: $HOME/.config/ttparser/base24]"),
+        arg!(--"gogh-output-folder" <gogh_output_folder> "The output folder for gogh themes [default: $HOME/.config/ttparser/gogh]"),
+        arg!(--"vscode-output-folder" <vscode_output_folder> "The output folder for vscode color customizations [default: $HOME/.config/ttparser/vscode]"),
+        arg!(--"neovim-output-folder" <neovim_output_folder> "The output folder for neovim lua [default: $HOME/.config/nvim/colors]"),
+        arg!(--"vim-output-folder" <vim_output_folder> "The output folder for vimscript [default: $HOME/.vim/colors]"),
+        arg!(--"vt-output-folder" <vt_output_folder> "The output folder for the linux console setvtrgb file [default: $HOME/.config/ttparser/vt]"),
+        arg!(--"vt-kernel-cmdline" "Also write the vt.default_red/grn/blu kernel command line for the linux console"),
+        arg!(--"svg-output-folder" <svg_output_folder> "The output folder for svg previews [default: .]"),
+        arg!(--"html-output-folder" <html_output_folder> "The output folder for html previews [default: .]"),
+        arg!(--"png-output-folder" <png_output_folder> "The output folder for png previews [default: .]"),
+        arg!(--"fix-contrast" [ratio] "Raise the contrast of the foreground and ANSI colors against the background to a WCAG ratio before writing [default: 3]")
+            .value_parser(value_parser!(f64))
+            .default_missing_value("3"),
+        arg!(--"generate-brights" <mode> "Derive the bright colors 8-15 and the dim colors from 0-7, only the missing ones, also the brights identical to their normal color, or always")
+            .value_parser(["missing", "identical", "always", "never"])
+            .default_value("missing"),
+        arg!(--"bright-strength" <amount> "OKLCH lightness step between the normal, bright and dim colors (0 to 1)").value_parser(value_parser!(f64)).default_value("0.1"),
+        arg!(--"opposite-variant" "Write the light variant of a dark theme (or the dark variant of a light one), suffixing the theme name with -light or -dark"),
+        arg!(--lighten <amount> "Raise the OKLCH lightness of the colors (0 to 1)").value_parser(value_parser!(f64)).action(ArgAction::Append),
+        arg!(--darken <amount> "Lower the OKLCH lightness of the colors (0 to 1)").value_parser(value_parser!(f64)).action(ArgAction::Append),
+        arg!(--saturate <amount> "Scale the chroma of the colors by 1 + amount, negative to desaturate").value_parser(value_parser!(f64)).action(ArgAction::Append).allow_negative_numbers(true),
+        arg!(--"hue-rotate" <degrees> "Rotate the hue of the colors").value_parser(value_parser!(f64)).action(ArgAction::Append).allow_negative_numbers(true),
+        arg!(--warmer <amount> "Shift the colors towards orange (0 to 1)").value_parser(value_parser!(f64)).action(ArgAction::Append),
+        arg!(--cooler <amount> "Shift the colors towards blue (0 to 1)").value_parser(value_parser!(f64)).action(ArgAction::Append),
+        arg!(--grayscale "Remove the chroma of the colors"),
+        arg!(--"transform-roles" <"palette,background,foreground,cursor,selection,all"> "The colors the transforms apply to")
+            .value_delimiter(',')
+            .value_parser(["palette", "background", "foreground", "cursor", "selection", "all"])
+            .ignore_case(true)
+            .default_value("all"),
+        arg!(--daltonize <deficiency> "Shift the ANSI colors so they stay distinguishable with a color vision deficiency")
+            .value_parser(["protanopia", "deuteranopia", "tritanopia"]),
+        arg!(-o --"terminal-output" <"format[=folder],..."> "The terminal outputs, any of foot, alacritty, kitty, xresources, gnome-terminal, konsole, base16, base24, gogh, vscode, neovim, vim, vt, svg, html and png, or all of them, each with an optional output folder")
+            .value_delimiter(',')
+            .default_value("foot,alacritty,kitty"),
+    ]
 }
 
 pub fn parse_args() -> Options {
 let home = env::var("HOME").ok().unwrap();
 
-    let matches = command!("TTparser")
-        .arg(
-            arg!(-i --"term-input" <"kitty,alacritty"> "Terminal input name").required(true)
-        )
-        .arg(
-            arg!(-f --"term-input-file" <term_input_file> "The theme file for the terminal input")
-                .required(true)
-        )
-        .arg(
-            arg!(-n --"theme-name" <theme_name> "The theme name")
-                .default_value("Theme ported with TTParser."),
+    let mut cli = command!("TTparser")
+        .subcommand_negates_reqs(true)
+        .args(input_args())
+        .arg(simulate_arg())
+        .subcommand(
+            Command::new("apply")
+                .about("Print OSC escape sequences applying the theme to the running terminal")
+                .args(input_args().map(|arg| arg.required(false)))
+                .arg(arg!(--script "Print a shell script of printf lines instead of the raw sequences"))
+                .arg(arg!(--reset "Print the sequences resetting the terminal colors instead"))
         )
-        .arg(
-            arg!(--"foot-output-folder" <foot_output_folder> "The output folder for foot [default: $HOME/.config/foot]")
+        .subcommand(
+            Command::new("check")
+                .visible_alias("lint")
+                .about("Audit the contrast of the theme, exiting with 1 when a pair is below its minimum")
+                .args(input_args())
+                .arg(arg!(--apca "Use APCA lightness contrast (Lc) instead of WCAG 2.x ratios"))
+                .arg(arg!(--"min-foreground" <contrast> "Minimum for foreground on background [default: 4.5, apca: 75]").value_parser(value_parser!(f64)))
+                .arg(arg!(--"min-palette" <contrast> "Minimum for the ANSI colors on background [default: 3, apca: 45]").value_parser(value_parser!(f64)))
+                .arg(arg!(--"min-cursor" <contrast> "Minimum for the cursor text on the cursor [default: 3, apca: 30]").value_parser(value_parser!(f64)))
+                .arg(arg!(--"min-selection" <contrast> "Minimum for the selection text on the selection [default: 4.5, apca: 60]").value_parser(value_parser!(f64)))
+                .arg(arg!(--cvd "Also report the ANSI colors that can't be told apart with protanopia, deuteranopia or tritanopia"))
+                .arg(arg!(--"min-cvd-distance" <distance> "Minimum OKLab distance between ANSI colors for --cvd").value_parser(value_parser!(f64)).default_value("0.05"))
+                .arg(simulate_arg())
         )
-        .arg(
-            arg!(--"alacritty-output-folder" <alacritty_output_folder> "The output folder for alacritty [default: $HOME/.config/alacritty]")
+        .subcommand(
+            Command::new("preview")
+                .about("Render the theme in the current terminal with 24-bit colors")
+                .args(input_args())
+                .arg(simulate_arg())
         )
-        .arg(
-            arg!(--"kitty-output-folder" <kitty_output_folder> "The output folder for kitty [default: $HOME/.config/kitty]")
-                    
+        .subcommand(
+            Command::new("generate")
+                .about("Generate a theme from an accent color or the dominant colors of an image, written like a conversion")
+                .arg(arg!(--accent <color> "The accent color, as hex"))
+                .arg(arg!(--image <image> "A png or jpeg image, a wallpaper for instance"))
+                .group(ArgGroup::new("seed").args(["accent", "image"]).required(true))
+                .arg(arg!(--variant <variant> "Dark or light theme [default: dark for an accent, the lightness of an image]").value_parser(["dark", "light"]))
+                .args(output_args())
+                .arg(simulate_arg())
         )
-        .arg(
-            arg!(-o --"terminal-output" <"all, alacritty, foot, kitty"> "The terminal output")
-                .default_value("all")
-                    
-        )
-        .get_matches();
+        .args(output_args());
+    let matches = cli.get_matches_mut();
 
-    let term_input = matches.get_one::<String>("term-input").unwrap().to_lowercase();
-    let term_input_file = matches.get_one::<String>("term-input-file").unwrap().to_lowercase();
-    let theme_name = matches.get_one::<String>("theme-name").unwrap().to_lowercase();
-    let kitty_output_folder = matches.get_one::<String>("kitty-output-folder").unwrap_or(&format!("{}/.config/kitty", home)).to_lowercase();
-    let foot_output_folder = matches.get_one::<String>("foot-output-folder").unwrap_or(&format!("{}/.config/foot", home)).to_lowercase();
-    let alacritty_output_folder = matches.get_one::<String>("alacritty-output-folder").unwrap_or(&format!("{}/.config/alacritty", home)).to_lowercase();
-    let terminal_output = matches.get_one::<String>("terminal-output").unwrap().to_lowercase();
+    let (command, input_matches) = matches.subcommand().unwrap_or(("convert", &matches));
+    let command = command.to_string();
+    let script = command == "apply" && input_matches.get_flag("script");
+    let reset = command == "apply" && input_matches.get_flag("reset");
+    let apca = command == "check" && input_matches.get_flag("apca");
+    let mut contrast_thresholds = match apca {
+        true => ContrastThresholds::apca(),
+        false => ContrastThresholds::wcag(),
+    };
+    if command == "check" {
+        for (id, threshold) in [
+            ("min-foreground", &mut contrast_thresholds.foreground),
+            ("min-palette", &mut contrast_thresholds.palette),
+            ("min-cursor", &mut contrast_thresholds.cursor),
+            ("min-selection", &mut contrast_thresholds.selection),
+        ] {
+            if let Some(minimum) = input_matches.get_one::<f64>(id) {
+                *threshold = *minimum;
+            }
+        }
+    }
+    // generate has no input, and `apply --reset` is the only other way to get here without one
+    let (term_input, term_input_file) = match command == "generate" {
+        true => (String::new(), String::new()),
+        false => match (input_matches.get_one::<String>("term-input"), input_matches.get_one::<String>("term-input-file")) {
+            (Some(term_input), Some(term_input_file)) => (term_input.to_lowercase(), term_input_file.to_string()),
+            _ if reset => (String::new(), String::new()),
+            _ => cli.error(ErrorKind::MissingRequiredArgument, "--term-input-file is required (it defaults to /dev/tty for terminal)").exit(),
+        },
+    };
+    // a single theme file is guessed right away, the ones of a folder or glob one by one
+    let term_input = match term_input == "auto" && !Path::new(&term_input_file).is_dir() && !term_input_file.contains(['*', '?']) {
+        true => match detect_theme_format(&term_input_file) {
+            Some(term_input) => term_input.to_string(),
+            None => cli.error(ErrorKind::InvalidValue, format!("can't read or guess the format of {}, set it with --term-input", term_input_file)).exit(),
+        },
+        false => term_input,
+    };
+    let output_matches = match command.as_str() {
+        "generate" => input_matches,
+        _ => &matches,
+    };
+    let generate_accent = match command == "generate" {
+        true => input_matches.get_one::<String>("accent").map(|accent| Rgb::from_hex(accent).unwrap_or_else(|| cli.error(ErrorKind::InvalidValue, format!("invalid accent color {}", accent)).exit())),
+        false => None,
+    };
+    let generate_image = match command == "generate" {
+        true => input_matches.get_one::<String>("image").cloned(),
+        false => None,
+    };
+    let generate_dark = match command == "generate" {
+        true => input_matches.get_one::<String>("variant").map(|variant| variant == "dark"),
+        false => None,
+    };
+    let theme_name = output_matches.get_one::<String>("theme-name").unwrap().clone();
+    let explicit_theme_name = output_matches.value_source("theme-name") == Some(ValueSource::CommandLine);
+    if explicit_theme_name && (Path::new(&term_input_file).is_dir() || term_input_file.contains(['*', '?'])) {
+        cli.error(ErrorKind::ArgumentConflict, "--theme-name can't be used with a folder or glob, every theme is named after its file").exit();
+    }
+    let mut output_folders: HashMap<String, String> = HashMap::new();
+    for (output, default_folder) in [
+        ("foot", format!("{}/.config/foot", home)),
+        ("alacritty", format!("{}/.config/alacritty", home)),
+        ("kitty", format!("{}/.config/kitty", home)),
+        ("xresources", format!("{}/.Xresources.d", home)),
+        ("gnome-terminal", format!("{}/.config/ttparser/gnome-terminal", home)),
+        ("konsole", format!("{}/.local/share/konsole", home)),
+        ("base16", format!("{}/.config/ttparser/base16", home)),
+        ("base24", format!("{}/.config/ttparser/base24", home)),
+        ("gogh", format!("{}/.config/ttparser/gogh", home)),
+        ("vscode", format!("{}/.config/ttparser/vscode", home)),
+        ("neovim", format!("{}/.config/nvim/colors", home)),
+        ("vim", format!("{}/.vim/colors", home)),
+        ("vt", format!("{}/.config/ttparser/vt", home)),
+        ("svg", ".".to_string()),
+        ("html", ".".to_string()),
+        ("png", ".".to_string()),
+    ] {
+        let output_folder = output_matches.get_one::<String>(&format!("{}-output-folder", output)).cloned().unwrap_or(default_folder);
+        output_folders.insert(output.to_string(), output_folder);
+    }
+    let vt_kernel_cmdline = output_matches.get_flag("vt-kernel-cmdline");
+    let fix_contrast = output_matches.get_one::<f64>("fix-contrast").copied();
+    let opposite_variant = output_matches.get_flag("opposite-variant");
+    let generate_brights = output_matches.get_one::<String>("generate-brights").unwrap().to_string();
+    let bright_strength = *output_matches.get_one::<f64>("bright-strength").unwrap();
+    // the transforms run in the order they were given
+    let mut transforms: Vec<(usize, ColorTransform)> = Vec::new();
+    for id in ["lighten", "darken", "saturate", "hue-rotate", "warmer", "cooler"] {
+        if let (Some(amounts), Some(indices)) = (output_matches.get_many::<f64>(id), output_matches.indices_of(id)) {
+            for (amount, index) in amounts.zip(indices) {
+                let transform = match id {
+                    "lighten" => ColorTransform::Lighten(*amount),
+                    "darken" => ColorTransform::Darken(*amount),
+                    "saturate" => ColorTransform::Saturate(*amount),
+                    "hue-rotate" => ColorTransform::HueRotate(*amount),
+                    "warmer" => ColorTransform::Warmer(*amount),
+                    _ => ColorTransform::Warmer(-*amount),
+                };
+                transforms.push((index, transform));
+            }
+        }
+    }
+    if let Some(index) = output_matches.get_flag("grayscale").then(|| output_matches.index_of("grayscale")).flatten() {
+        transforms.push((index, ColorTransform::Grayscale));
+    }
+    transforms.sort_by_key(|(index, _)| *index);
+    let transforms = transforms.into_iter().map(|(_, transform)| transform).collect();
+    let transform_roles = output_matches.get_many::<String>("transform-roles").unwrap().map(|role| role.to_lowercase()).collect();
+    // apply has no --simulate
+    let simulate = input_matches.try_get_one::<String>("simulate").ok().flatten().and_then(|deficiency| Deficiency::from_str(deficiency));
+    let daltonize = output_matches.get_one::<String>("daltonize").and_then(|deficiency| Deficiency::from_str(deficiency));
+    let cvd = command == "check" && input_matches.get_flag("cvd");
+    let min_cvd_distance = match command == "check" {
+        true => *input_matches.get_one::<f64>("min-cvd-distance").unwrap(),
+        false => 0.0,
+    };
+    // `format=folder` entries also set the folder of the format, `all` is every writer
+    let mut terminal_outputs: Vec<String> = Vec::new();
+    for output in output_matches.get_many::<String>("terminal-output").unwrap() {
+        let (output, output_folder) = match output.split_once('=') {
+            Some((output, output_folder)) => (output.to_lowercase(), Some(output_folder.to_string())),
+            None => (output.to_lowercase(), None),
+        };
+        let outputs_all = output == "all";
+        let outputs: Vec<String> = match output.as_str() {
+            "all" => OUTPUT_FORMATS.iter().map(|output| output.to_string()).collect(),
+            output if OUTPUT_FORMATS.contains(&output) => vec![output.to_string()],
+            _ => cli.error(ErrorKind::InvalidValue, format!("unknown output {}, expected all or one of {}", output, OUTPUT_FORMATS.join(", "))).exit(),
+        };
+        for output in outputs {
+            // with all, every format gets its own folder in there since some share extensions
+            match (&output_folder, outputs_all) {
+                (Some(output_folder), true) => output_folders.insert(output.clone(), format!("{}/{}", output_folder, output)),
+                (Some(output_folder), false) => output_folders.insert(output.clone(), output_folder.to_string()),
+                (None, _) => None,
+            };
+            if !terminal_outputs.contains(&output) {
+                terminal_outputs.push(output);
+            }
+        }
+    }
+    // without an explicit list (or with all), the format of the input is not written again
+    let skip_input_format = output_matches.value_source("terminal-output") != Some(ValueSource::CommandLine) || output_matches.get_many::<String>("terminal-output").unwrap().any(|output| output.split('=').next().unwrap().to_lowercase() == "all");
 
     Options {
+        command,
+        script,
+        reset,
+        apca,
+        contrast_thresholds,
+        fix_contrast,
+        opposite_variant,
+        generate_brights,
+        bright_strength,
+        transforms,
+        transform_roles,
+        simulate,
+        daltonize,
+        cvd,
+        min_cvd_distance,
+        generate_accent,
+        generate_image,
+        generate_dark,
         term_input,
         term_input_file,
-        foot_output_folder,
-        kitty_output_folder,
-        terminal_output,
-        alacritty_output_folder,
+        output_folders,
+        vt_kernel_cmdline,
+        terminal_outputs,
+        skip_input_format,
         theme_name,
+        explicit_theme_name,
     }
 }
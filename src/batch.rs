@@ -0,0 +1,129 @@
+use crate::theme_creator::theme_slug;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// `*` matches any run of characters and `?` a single one
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            wildcard_match(&pattern[1..], name)
+                || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// The theme files of a folder, or of a glob on file names (`themes/*.conf`),
+/// sorted. `None` when `input` is a single file.
+pub fn batch_input_paths(input: &str) -> Option<Result<Vec<PathBuf>, String>> {
+    let input_path = Path::new(input);
+    let (folder, pattern) = match input_path.is_dir() {
+        true => (input_path, None),
+        false => {
+            let file_name = input_path.file_name()?.to_string_lossy().to_string();
+            if !file_name.contains(['*', '?']) {
+                return None;
+            }
+            let folder = match input_path.parent() {
+                Some(folder) if !folder.as_os_str().is_empty() => folder,
+                _ => Path::new("."),
+            };
+            (folder, Some(file_name.chars().collect::<Vec<char>>()))
+        }
+    };
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return Some(Err("Error reading folder".to_string())),
+    };
+    let mut theme_paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| match &pattern {
+            Some(pattern) => {
+                let name: Vec<char> = path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .chars()
+                    .collect();
+                wildcard_match(pattern, &name)
+            }
+            None => true,
+        })
+        .collect();
+    theme_paths.sort();
+    Some(Ok(theme_paths))
+}
+
+/// The file name of every theme, from the slug of its file stem, with `-2`, `-3`...
+/// added when two themes end up with the same one (`Nord.conf` and `nord.yaml`).
+pub fn batch_theme_names(theme_paths: &[PathBuf]) -> Vec<String> {
+    let mut theme_names: Vec<String> = Vec::new();
+    for theme_path in theme_paths {
+        let slug = theme_slug(&theme_path.file_stem().unwrap_or_default().to_string_lossy());
+        let mut theme_name = slug.clone();
+        let mut number = 2;
+        while theme_names.contains(&theme_name) {
+            theme_name = format!("{}-{}", slug, number);
+            number += 1;
+        }
+        theme_names.push(theme_name);
+    }
+    theme_names
+}
+
+/// Runs `convert` on every path, spread over as many threads as there are cores.
+/// A failing or panicking theme does not stop the others, every result comes back
+/// in the order of `theme_paths`.
+pub fn run_batch<F>(theme_paths: &[PathBuf], convert: F) -> Vec<Result<String, String>>
+where
+    F: Fn(&Path) -> Result<String, String> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<String, String>>>> =
+        Mutex::new(vec![None; theme_paths.len()]);
+    let workers = thread::available_parallelism()
+        .map(|workers| workers.get())
+        .unwrap_or(4)
+        .min(theme_paths.len());
+
+    // the panic messages of the parsers end up in the summary instead
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(theme_path) = theme_paths.get(index) else {
+                    break;
+                };
+                let result = panic::catch_unwind(AssertUnwindSafe(|| convert(theme_path)))
+                    .unwrap_or_else(|payload| {
+                        Err(match payload.downcast_ref::<&str>() {
+                            Some(message) => message.to_string(),
+                            None => payload
+                                .downcast_ref::<String>()
+                                .cloned()
+                                .unwrap_or("Unknown error".to_string()),
+                        })
+                    });
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    panic::set_hook(default_hook);
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.unwrap_or(Err("Not converted".to_string())))
+        .collect()
+}
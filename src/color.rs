@@ -0,0 +1,215 @@
+/// OKLCH: perceptual lightness (0.0 to 1.0), chroma and hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    /// Parses `rgb`, `rrggbb` or `rrrrggggbbbb` hex, with or without the leading `#`.
+    pub fn from_hex(hex: &str) -> Option<Rgb> {
+        let hex = hex.trim().trim_start_matches('#');
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) || !hex.len().is_multiple_of(3) {
+            return None;
+        }
+        let width = hex.len() / 3;
+        if width == 0 || width > 4 {
+            return None;
+        }
+        let component = |index: usize| scale_hex(&hex[index * width..(index + 1) * width]);
+        Some(Rgb {
+            r: component(0)?,
+            g: component(1)?,
+            b: component(2)?,
+        })
+    }
+
+    /// Parses the X11 `rgb:r/g/b` syntax (1 to 4 hex digits per component) or a hex color.
+    pub fn from_x11(spec: &str) -> Option<Rgb> {
+        let spec = spec.trim();
+        match spec.strip_prefix("rgb:") {
+            Some(components) => {
+                let components: Vec<&str> = components.split('/').collect();
+                if components.len() != 3 {
+                    return None;
+                }
+                Some(Rgb {
+                    r: scale_hex(components[0])?,
+                    g: scale_hex(components[1])?,
+                    b: scale_hex(components[2])?,
+                })
+            }
+            None => Rgb::from_hex(spec),
+        }
+    }
+
+    /// Parses `r,g,b` decimal components, optionally wrapped in `rgb(...)`.
+    pub fn from_decimal(spec: &str) -> Option<Rgb> {
+        let spec = spec.trim();
+        let spec = spec
+            .strip_prefix("rgb(")
+            .and_then(|spec| spec.strip_suffix(')'))
+            .unwrap_or(spec);
+        let components: Vec<u8> = spec
+            .split(',')
+            .map(|component| component.trim().parse::<u8>())
+            .collect::<Result<_, _>>()
+            .ok()?;
+        match components[..] {
+            [r, g, b] => Some(Rgb { r, g, b }),
+            _ => None,
+        }
+    }
+
+    /// Linear interpolation towards `other`, `amount` going from 0.0 (self) to 1.0 (other).
+    pub fn mix(self, other: Rgb, amount: f64) -> Rgb {
+        let channel = |from: u8, to: u8| {
+            (from as f64 + (to as f64 - from as f64) * amount.clamp(0.0, 1.0)).round() as u8
+        };
+        Rgb {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+
+    /// WCAG 2.x relative luminance, from 0.0 (black) to 1.0 (white).
+    pub fn luminance(self) -> f64 {
+        let [r, g, b] = self.to_linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// X11 `rgb:rr/gg/bb`, the syntax used by OSC color sequences.
+    pub fn to_x11(self) -> String {
+        format!("rgb:{:02x}/{:02x}/{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Linear light sRGB components, from 0.0 to 1.0.
+    pub fn to_linear(self) -> [f64; 3] {
+        let linear = |value: u8| {
+            let value = value as f64 / 255.0;
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        [linear(self.r), linear(self.g), linear(self.b)]
+    }
+
+    /// From linear light sRGB components, clamping the ones outside 0.0 to 1.0.
+    pub fn from_linear(linear: [f64; 3]) -> Rgb {
+        let gamma = |value: f64| {
+            let value = value.clamp(0.0, 1.0);
+            let value = if value <= 0.0031308 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            };
+            (value * 255.0).round() as u8
+        };
+        Rgb {
+            r: gamma(linear[0]),
+            g: gamma(linear[1]),
+            b: gamma(linear[2]),
+        }
+    }
+
+    /// OKLab lightness, a (green-red) and b (blue-yellow).
+    pub fn to_oklab(self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        [
+            0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        ]
+    }
+
+    /// Perceptual difference, the euclidean distance in OKLab (about 0.02 is just noticeable).
+    pub fn distance(self, other: Rgb) -> f64 {
+        let (a, b) = (self.to_oklab(), other.to_oklab());
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+    }
+
+    pub fn to_oklch(self) -> Oklch {
+        let [lightness, a, b] = self.to_oklab();
+        Oklch {
+            l: lightness,
+            c: (a * a + b * b).sqrt(),
+            h: b.atan2(a).to_degrees().rem_euclid(360.0),
+        }
+    }
+
+    /// Lowercase `rrggbb` without the leading `#`, the way `Base16Colors` stores colors.
+    pub fn to_hex(self) -> String {
+        format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+// scales a 1 to 4 digit hex component to 8 bits, as X11 does for `rgb:` colors
+fn scale_hex(component: &str) -> Option<u8> {
+    if component.is_empty()
+        || component.len() > 4
+        || !component.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    let value = u32::from_str_radix(component, 16).ok()?;
+    let max = (1u32 << (4 * component.len())) - 1;
+    Some(((value * 255 + max / 2) / max) as u8)
+}
+
+impl Oklch {
+    // linear sRGB, components outside 0.0..=1.0 when the color is out of gamut
+    fn to_linear_srgb(self) -> [f64; 3] {
+        let (a, b) = (
+            self.c * self.h.to_radians().cos(),
+            self.c * self.h.to_radians().sin(),
+        );
+        let l = (self.l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (self.l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (self.l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ]
+    }
+
+    /// Back to sRGB, lowering the chroma (keeping lightness and hue) until the color fits.
+    pub fn to_rgb(self) -> Rgb {
+        let in_gamut = |color: Oklch| {
+            color
+                .to_linear_srgb()
+                .iter()
+                .all(|channel| (-0.0001..=1.0001).contains(channel))
+        };
+        let mut color = Oklch {
+            l: self.l.clamp(0.0, 1.0),
+            ..self
+        };
+        if !in_gamut(color) {
+            let (mut low, mut high) = (0.0, color.c);
+            for _ in 0..24 {
+                let chroma = (low + high) / 2.0;
+                match in_gamut(Oklch { c: chroma, ..color }) {
+                    true => low = chroma,
+                    false => high = chroma,
+                }
+            }
+            color.c = low;
+        }
+        Rgb::from_linear(color.to_linear_srgb())
+    }
+}
@@ -0,0 +1,168 @@
+use crate::color::Rgb;
+use crate::theme_parser::Base16Colors;
+
+/// WCAG 2.x contrast ratio, from 1.0 (same luminance) to 21.0 (black on white).
+pub fn contrast_ratio(a: Rgb, b: Rgb) -> f64 {
+    let (lighter, darker) = match a.luminance() > b.luminance() {
+        true => (a.luminance(), b.luminance()),
+        false => (b.luminance(), a.luminance()),
+    };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+/// APCA (0.0.98G-4g) lightness contrast `Lc` of `text` drawn on `background`,
+/// positive for dark text on light backgrounds and negative the other way around.
+pub fn apca_contrast(text: Rgb, background: Rgb) -> f64 {
+    let screen_luminance = |color: Rgb| {
+        let channel = |value: u8| (value as f64 / 255.0).powf(2.4);
+        let y = 0.2126729 * channel(color.r)
+            + 0.7151522 * channel(color.g)
+            + 0.0721750 * channel(color.b);
+        // soft clamp of the near black colors
+        match y < 0.022 {
+            true => y + (0.022 - y).powf(1.414),
+            false => y,
+        }
+    };
+    let text = screen_luminance(text);
+    let background = screen_luminance(background);
+    if (background - text).abs() < 0.0005 {
+        return 0.0;
+    }
+    let contrast = match background > text {
+        true => {
+            let sapc = (background.powf(0.56) - text.powf(0.57)) * 1.14;
+            if sapc < 0.1 {
+                0.0
+            } else {
+                sapc - 0.027
+            }
+        }
+        false => {
+            let sapc = (background.powf(0.65) - text.powf(0.62)) * 1.14;
+            if sapc > -0.1 {
+                0.0
+            } else {
+                sapc + 0.027
+            }
+        }
+    };
+    contrast * 100.0
+}
+
+/// `color0` on dark themes and `color7`/`color15` on light ones are meant to blend
+/// into the background, contrast rules leave them out.
+pub fn blends_into_background(color_name: &str, background: Rgb) -> bool {
+    match background.luminance() < 0.5 {
+        true => color_name == "color0",
+        false => color_name == "color7" || color_name == "color15",
+    }
+}
+
+/// Minimum contrast per kind of pair, WCAG ratios or APCA `Lc` depending on the metric.
+#[derive(Debug, Clone, Copy)]
+pub struct ContrastThresholds {
+    pub foreground: f64,
+    pub palette: f64,
+    pub cursor: f64,
+    pub selection: f64,
+}
+
+impl ContrastThresholds {
+    pub fn wcag() -> ContrastThresholds {
+        ContrastThresholds {
+            foreground: 4.5,
+            palette: 3.0,
+            cursor: 3.0,
+            selection: 4.5,
+        }
+    }
+
+    pub fn apca() -> ContrastThresholds {
+        ContrastThresholds {
+            foreground: 75.0,
+            palette: 45.0,
+            cursor: 30.0,
+            selection: 60.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ContrastCheck {
+    pub label: String,
+    pub foreground: Rgb,
+    pub background: Rgb,
+    pub contrast: f64,
+    pub minimum: f64,
+}
+
+impl ContrastCheck {
+    pub fn passed(&self) -> bool {
+        self.contrast >= self.minimum
+    }
+}
+
+/// Checks foreground/background, every ANSI color on the background (but the ones
+/// blending into it), the cursor and the selection.
+pub fn audit_contrast(
+    base16_colors: &Base16Colors,
+    thresholds: &ContrastThresholds,
+    apca: bool,
+) -> Vec<ContrastCheck> {
+    let color = |color_name: &str| {
+        base16_colors
+            .get(color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+    };
+    let background = color("background").unwrap();
+
+    let mut pairs: Vec<(String, &str, &str, f64)> = vec![(
+        "foreground on background".to_string(),
+        "foreground",
+        "background",
+        thresholds.foreground,
+    )];
+    let palette_names: Vec<String> = (0..16).map(|number| format!("color{}", number)).collect();
+    for color_name in palette_names.iter() {
+        if !blends_into_background(color_name, background) {
+            pairs.push((
+                format!("{} on background", color_name),
+                color_name,
+                "background",
+                thresholds.palette,
+            ));
+        }
+    }
+    pairs.push((
+        "cursor_fg on cursor".to_string(),
+        "cursor_fg",
+        "cursor",
+        thresholds.cursor,
+    ));
+    pairs.push((
+        "selection_foreground on selection_background".to_string(),
+        "selection_foreground",
+        "selection_background",
+        thresholds.selection,
+    ));
+
+    pairs
+        .into_iter()
+        .filter_map(|(label, foreground_name, background_name, minimum)| {
+            let foreground = color(foreground_name)?;
+            let background = color(background_name)?;
+            let contrast = match apca {
+                true => apca_contrast(foreground, background).abs(),
+                false => contrast_ratio(foreground, background),
+            };
+            Some(ContrastCheck {
+                label,
+                foreground,
+                background,
+                contrast,
+                minimum,
+            })
+        })
+        .collect()
+}
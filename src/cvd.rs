@@ -0,0 +1,257 @@
+use crate::color::Rgb;
+use crate::theme_parser::Base16Colors;
+use crate::transform::role_color_names;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl Deficiency {
+    pub fn from_str(deficiency_str: &str) -> Option<Deficiency> {
+        match deficiency_str {
+            "protanopia" => Some(Deficiency::Protanopia),
+            "deuteranopia" => Some(Deficiency::Deuteranopia),
+            "tritanopia" => Some(Deficiency::Tritanopia),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [Deficiency; 3] {
+        [
+            Deficiency::Protanopia,
+            Deficiency::Deuteranopia,
+            Deficiency::Tritanopia,
+        ]
+    }
+
+    // Machado, Oliveira and Fernandes (2009) at full severity, on linear sRGB
+    fn matrix(self) -> [[f64; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// How `color` looks to someone with this deficiency.
+    pub fn simulate(self, color: Rgb) -> Rgb {
+        let linear = color.to_linear();
+        let matrix = self.matrix();
+        Rgb::from_linear(
+            matrix.map(|row| row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]),
+        )
+    }
+
+    /// Fidaner's daltonization: the part of `color` lost to the deficiency is moved
+    /// onto the channels that are still seen.
+    pub fn daltonize(self, color: Rgb) -> Rgb {
+        let simulated = self.simulate(color);
+        let error = [
+            color.r as f64 - simulated.r as f64,
+            color.g as f64 - simulated.g as f64,
+            color.b as f64 - simulated.b as f64,
+        ];
+        let shift = match self {
+            Deficiency::Protanopia | Deficiency::Deuteranopia => {
+                [0.0, 0.7 * error[0] + error[1], 0.7 * error[0] + error[2]]
+            }
+            Deficiency::Tritanopia => [error[0] + 0.7 * error[2], error[1] + 0.7 * error[2], 0.0],
+        };
+        let channel =
+            |value: u8, shift: f64| (value as f64 + shift).round().clamp(0.0, 255.0) as u8;
+        Rgb {
+            r: channel(color.r, shift[0]),
+            g: channel(color.g, shift[1]),
+            b: channel(color.b, shift[2]),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+        }
+    }
+}
+
+/// Replaces every color of the theme by how it looks with the deficiency.
+pub fn simulate_colors(base16_colors: &mut Base16Colors, deficiency: Deficiency) {
+    for color_name in role_color_names("all") {
+        if let Some(color) = base16_colors
+            .get(&color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+        {
+            base16_colors.insert(color_name, deficiency.simulate(color).to_hex());
+        }
+    }
+}
+
+/// The distance under which `check --cvd` counts two ANSI colors as colliding by default.
+pub const MIN_CVD_DISTANCE: f64 = 0.05;
+
+// how many ANSI pairs collide, and by how much they fall short of the distance in total.
+// Pairs too close with normal vision count too, so merging two colors is no way out.
+fn collision_score(base16_colors: &Base16Colors) -> (usize, f64) {
+    let collisions = cvd_collisions(base16_colors, MIN_CVD_DISTANCE);
+    let shortfall = collisions
+        .iter()
+        .map(|collision| MIN_CVD_DISTANCE - collision.distance)
+        .sum();
+    let color = |number: usize| {
+        base16_colors
+            .get(&format!("color{}", number))
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+    };
+    let mut merged = 0;
+    for offset in [0, 8] {
+        for first in offset + 1..offset + 7 {
+            for second in first + 1..offset + 7 {
+                if let (Some(first_color), Some(second_color)) = (color(first), color(second)) {
+                    if first_color.distance(second_color) < MIN_CVD_DISTANCE {
+                        merged += 1;
+                    }
+                }
+            }
+        }
+    }
+    (collisions.len() + merged, shortfall)
+}
+
+fn better_score(score: (usize, f64), than: (usize, f64)) -> bool {
+    score.0 < than.0 || (score.0 == than.0 && score.1 < than.1 - 1e-6)
+}
+
+/// Daltonizes the ANSI palette, leaving background, foreground, cursor and selection alone.
+/// A color keeps its shift only when no more ANSI pairs collide with it (for any of the
+/// deficiencies), then the pairs still colliding are pulled apart in OKLCH lightness.
+pub fn daltonize_colors(base16_colors: &mut Base16Colors, deficiency: Deficiency) {
+    for color_name in role_color_names("palette") {
+        let Some(color) = base16_colors
+            .get(&color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+        else {
+            continue;
+        };
+        let collisions = collision_score(base16_colors).0;
+        let original = color.to_hex();
+        base16_colors.insert(color_name.clone(), deficiency.daltonize(color).to_hex());
+        if collision_score(base16_colors).0 > collisions {
+            base16_colors.insert(color_name, original);
+        }
+    }
+    separate_collisions(base16_colors);
+}
+
+// moves one color of a colliding pair at a time, keeping the move that removes the most
+// collisions, until none is left or no move helps. No color strays more than 0.12 in
+// lightness from where it started.
+fn separate_collisions(base16_colors: &mut Base16Colors) {
+    let starting_lightness: HashMap<String, f64> = role_color_names("palette")
+        .into_iter()
+        .filter_map(|color_name| {
+            let color = Rgb::from_hex(base16_colors.get(&color_name)?)?;
+            Some((color_name, color.to_oklch().l))
+        })
+        .collect();
+    for _ in 0..32 {
+        let collisions = cvd_collisions(base16_colors, MIN_CVD_DISTANCE);
+        if collisions.is_empty() {
+            return;
+        }
+        let mut best_score = collision_score(base16_colors);
+        let mut best_move: Option<(String, String)> = None;
+        for collision in collisions.iter() {
+            for color_name in [&collision.first, &collision.second] {
+                let original = base16_colors[color_name].clone();
+                let color = Rgb::from_hex(&original).unwrap().to_oklch();
+                let start = starting_lightness[color_name];
+                for step in [-0.08, -0.04, 0.04, 0.08] {
+                    let lightness = (color.l + step).clamp(0.0, 1.0);
+                    if (lightness - start).abs() > 0.12 {
+                        continue;
+                    }
+                    let mut moved = color;
+                    moved.l = lightness;
+                    let moved = moved.to_rgb().to_hex();
+                    base16_colors.insert(color_name.clone(), moved.clone());
+                    let score = collision_score(base16_colors);
+                    if better_score(score, best_score) {
+                        best_score = score;
+                        best_move = Some((color_name.clone(), moved));
+                    }
+                }
+                base16_colors.insert(color_name.clone(), original);
+            }
+        }
+        match best_move {
+            Some((color_name, moved)) => {
+                base16_colors.insert(color_name, moved);
+            }
+            None => return,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CvdCollision {
+    pub deficiency: Deficiency,
+    pub first: String,
+    pub second: String,
+    pub distance: f64,
+}
+
+/// Pairs of ANSI colors (red to cyan, normal ones together and bright ones together)
+/// that are told apart with normal vision but end up closer than `minimum_distance`
+/// (in OKLab) under one of the deficiencies.
+pub fn cvd_collisions(base16_colors: &Base16Colors, minimum_distance: f64) -> Vec<CvdCollision> {
+    let color = |number: usize| {
+        base16_colors
+            .get(&format!("color{}", number))
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+    };
+    let mut collisions: Vec<CvdCollision> = Vec::new();
+    for deficiency in Deficiency::all() {
+        for offset in [0, 8] {
+            for first in offset + 1..offset + 7 {
+                for second in first + 1..offset + 7 {
+                    let (Some(first_color), Some(second_color)) = (color(first), color(second))
+                    else {
+                        continue;
+                    };
+                    if first_color.distance(second_color) < minimum_distance {
+                        continue;
+                    }
+                    let distance = deficiency
+                        .simulate(first_color)
+                        .distance(deficiency.simulate(second_color));
+                    if distance < minimum_distance {
+                        collisions.push(CvdCollision {
+                            deficiency,
+                            first: format!("color{}", first),
+                            second: format!("color{}", second),
+                            distance,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    collisions
+}
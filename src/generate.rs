@@ -0,0 +1,229 @@
+use crate::color::{Oklch, Rgb};
+use crate::theme_parser::Base16Colors;
+use crate::transform::{generate_brights, generate_dims, BrightsMode};
+
+// OKLCH hues of red, green, yellow, blue, magenta and cyan (color1 to color6)
+const ANSI_HUES: [f64; 6] = [25.0, 140.0, 90.0, 255.0, 320.0, 195.0];
+
+// shortest signed angle from `from` to `to`, in degrees
+fn hue_difference(from: f64, to: f64) -> f64 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn oklch(l: f64, c: f64, h: f64) -> Oklch {
+    Oklch { l, c, h }
+}
+
+// the whole theme from a tint for the neutrals, an accent for the cursor and selection
+// and the six ANSI colors, every lightness fixed by the variant so the theme stays legible
+fn build_theme(
+    tint: Oklch,
+    accent: Oklch,
+    ansi_colors: [Oklch; 6],
+    dark: bool,
+    bright_strength: f64,
+) -> Base16Colors {
+    let neutral_chroma = tint.c.min(0.02);
+    let (background, foreground, black, white, ansi_lightness) = match dark {
+        true => (0.18, 0.88, 0.40, 0.80, 0.72),
+        false => (0.97, 0.28, 0.30, 0.86, 0.52),
+    };
+    let background = oklch(background, neutral_chroma, tint.h).to_rgb();
+    let foreground = oklch(foreground, neutral_chroma, tint.h).to_rgb();
+    let accent = oklch(ansi_lightness, accent.c, accent.h).to_rgb();
+
+    let mut base16_colors = Base16Colors::new();
+    base16_colors.insert("background".to_string(), background.to_hex());
+    base16_colors.insert("foreground".to_string(), foreground.to_hex());
+    base16_colors.insert("cursor".to_string(), accent.to_hex());
+    base16_colors.insert("cursor_fg".to_string(), background.to_hex());
+    base16_colors.insert(
+        "selection_background".to_string(),
+        background.mix(accent, 0.3).to_hex(),
+    );
+    base16_colors.insert("selection_foreground".to_string(), foreground.to_hex());
+    base16_colors.insert(
+        "color0".to_string(),
+        oklch(black, neutral_chroma, tint.h).to_rgb().to_hex(),
+    );
+    for (number, color) in ansi_colors.iter().enumerate() {
+        base16_colors.insert(
+            format!("color{}", number + 1),
+            oklch(ansi_lightness, color.c, color.h).to_rgb().to_hex(),
+        );
+    }
+    base16_colors.insert(
+        "color7".to_string(),
+        oklch(white, neutral_chroma, tint.h).to_rgb().to_hex(),
+    );
+    generate_brights(&mut base16_colors, bright_strength, BrightsMode::Always);
+    generate_dims(&mut base16_colors, bright_strength, true);
+    base16_colors
+}
+
+/// A theme around `accent`: the ANSI colors keep their usual hues, turned a little
+/// towards the accent (10 degrees at most) and at its chroma, and the closest one becomes the accent itself.
+pub fn theme_from_accent(accent: Rgb, dark: bool, bright_strength: f64) -> Base16Colors {
+    let accent = accent.to_oklch();
+    let chroma = accent.c.clamp(0.06, 0.18);
+    let closest = (0..6)
+        .min_by(|&a, &b| {
+            hue_difference(ANSI_HUES[a], accent.h)
+                .abs()
+                .total_cmp(&hue_difference(ANSI_HUES[b], accent.h).abs())
+        })
+        .unwrap();
+    let ansi_colors = std::array::from_fn(|number| match number == closest {
+        true => accent,
+        false => {
+            let turn = (0.15 * hue_difference(ANSI_HUES[number], accent.h)).clamp(-10.0, 10.0);
+            let hue = ANSI_HUES[number] + turn;
+            oklch(0.0, chroma, hue.rem_euclid(360.0))
+        }
+    });
+    build_theme(accent, accent, ansi_colors, dark, bright_strength)
+}
+
+// splits the pixels in `boxes` boxes along their widest channel, at the median,
+// and returns the average color of each box with its pixel count
+fn median_cut(pixels: Vec<Rgb>, boxes: usize) -> Vec<(Rgb, usize)> {
+    let channel = |pixel: &Rgb, index: usize| [pixel.r, pixel.g, pixel.b][index];
+    let widest = |pixels: &[Rgb]| {
+        (0..3)
+            .map(|index| {
+                let values = pixels.iter().map(|pixel| channel(pixel, index));
+                let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                (range, index)
+            })
+            .max()
+            .unwrap()
+    };
+    let mut buckets: Vec<Vec<Rgb>> = vec![pixels];
+    while buckets.len() < boxes {
+        let Some((position, (_, index))) = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.len() > 1)
+            .map(|(position, bucket)| (position, widest(bucket)))
+            .max_by_key(|(_, (range, _))| *range)
+        else {
+            break;
+        };
+        let mut bucket = buckets.swap_remove(position);
+        bucket.sort_by_key(|pixel| channel(pixel, index));
+        let upper = bucket.split_off(bucket.len() / 2);
+        buckets.push(bucket);
+        buckets.push(upper);
+    }
+    buckets
+        .into_iter()
+        .filter(|bucket| !bucket.is_empty())
+        .map(|bucket| {
+            let sum = |index: usize| {
+                let total: usize = bucket
+                    .iter()
+                    .map(|pixel| channel(pixel, index) as usize)
+                    .sum();
+                (total / bucket.len()) as u8
+            };
+            (
+                Rgb {
+                    r: sum(0),
+                    g: sum(1),
+                    b: sum(2),
+                },
+                bucket.len(),
+            )
+        })
+        .collect()
+}
+
+/// A theme from the dominant colors of a png or jpeg image (median cut, pywal-style).
+/// Each ANSI color turns towards the closest hue of the image, or keeps its usual hue
+/// when the image has nothing near it. `dark` defaults to the lightness of the image.
+pub fn theme_from_image(
+    path: &str,
+    dark: Option<bool>,
+    bright_strength: f64,
+) -> Result<Base16Colors, String> {
+    let image = image::open(path)
+        .map_err(|err| format!("Error reading image {}: {}", path, err))?
+        .to_rgb8();
+    // about 10000 pixels are plenty to find the dominant colors
+    let step = ((image.width() as usize * image.height() as usize) / 10000).max(1);
+    let pixels: Vec<Rgb> = image
+        .pixels()
+        .step_by(step)
+        .map(|pixel| Rgb {
+            r: pixel[0],
+            g: pixel[1],
+            b: pixel[2],
+        })
+        .collect();
+    if pixels.is_empty() {
+        return Err(format!("Error reading image {}: no pixels", path));
+    }
+    let total = pixels.len() as f64;
+    let dominant: Vec<(Oklch, usize)> = median_cut(pixels, 16)
+        .into_iter()
+        .map(|(color, count)| (color.to_oklch(), count))
+        .collect();
+
+    let lightness = dominant
+        .iter()
+        .map(|(color, count)| color.l * *count as f64)
+        .sum::<f64>()
+        / total;
+    let dark = dark.unwrap_or(lightness < 0.6);
+    // the neutrals are tinted by the most common color, the accent is the most vivid one
+    let tint = dominant.iter().max_by_key(|(_, count)| *count).unwrap().0;
+    let accent = dominant
+        .iter()
+        .map(|(color, count)| (color, color.c * (*count as f64 / total).sqrt()))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap()
+        .0;
+    let vivid: Vec<&Oklch> = dominant
+        .iter()
+        .map(|(color, _)| color)
+        .filter(|color| color.c > 0.04)
+        .collect();
+    let chroma = match vivid.is_empty() {
+        true => 0.1,
+        false => {
+            (vivid.iter().map(|color| color.c).sum::<f64>() / vivid.len() as f64).clamp(0.06, 0.18)
+        }
+    };
+    // a dominant color serves one ANSI color at most, and only pulls its hue halfway
+    let mut used: Vec<usize> = Vec::new();
+    let ansi_colors = ANSI_HUES.map(|hue| {
+        let closest = (0..vivid.len())
+            .filter(|index| !used.contains(index))
+            .filter(|&index| hue_difference(hue, vivid[index].h).abs() < 45.0)
+            .min_by(|&a, &b| {
+                hue_difference(hue, vivid[a].h)
+                    .abs()
+                    .total_cmp(&hue_difference(hue, vivid[b].h).abs())
+            });
+        match closest {
+            Some(index) => {
+                used.push(index);
+                let color = vivid[index];
+                let hue = hue + 0.5 * hue_difference(hue, color.h);
+                oklch(0.0, color.c.clamp(0.06, 0.2), hue.rem_euclid(360.0))
+            }
+            None => oklch(0.0, chroma, hue),
+        }
+    });
+    let accent = match accent.c > 0.04 {
+        true => *accent,
+        false => ansi_colors[3],
+    };
+    Ok(build_theme(
+        tint,
+        accent,
+        ansi_colors,
+        dark,
+        bright_strength,
+    ))
+}
@@ -1,86 +1,409 @@
 mod args;
+mod batch;
+mod color;
+mod contrast;
+mod cvd;
+mod generate;
+mod osc;
+mod preview;
 mod theme_creator;
 mod theme_parser;
+mod transform;
 use args::{parse_args, Options};
-use theme_creator::{create_alacritty_theme, create_foot_theme, create_kitty_theeme};
+use batch::{batch_input_paths, batch_theme_names, run_batch};
+use color::Rgb;
+use contrast::audit_contrast;
+use cvd::{cvd_collisions, daltonize_colors, simulate_colors};
+use generate::{theme_from_accent, theme_from_image};
+use osc::{osc_reset_sequences, osc_shell_script, osc_theme_sequences};
+use preview::terminal_preview;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use theme_creator::{
+    create_alacritty_theme, create_base16_theme, create_base24_theme, create_foot_theme,
+    create_gnome_terminal_theme, create_gogh_theme, create_html_theme, create_kitty_theeme,
+    create_konsole_theme, create_neovim_theme, create_png_theme, create_svg_theme,
+    create_vim_theme, create_vscode_theme, create_vt_kernel_cmdline, create_vt_theme,
+    create_xresources_theme, output_file_name, theme_slug,
+};
 use theme_parser::{
-    alacritty_colors_to_base16_colors, kitty_colors_to_base16_colors, Base16Colors,
+    alacritty_colors_to_base16_colors, base16_scheme_to_base16_colors, detect_theme_format,
+    gnome_terminal_colors_to_base16_colors, gogh_theme_to_base16_colors,
+    kitty_colors_to_base16_colors, konsole_colors_to_base16_colors, missing_colors,
+    pywal_colors_to_base16_colors, terminal_colors_to_base16_colors, vim_colors_to_base16_colors,
+    vscode_colors_to_base16_colors, xresources_colors_to_base16_colors, Theme,
+};
+use transform::{
+    fix_contrast, generate_brights, generate_dims, opposite_variant, transform_colors, BrightsMode,
 };
 
-fn create_base16_colors(term_input_name: &String, term_input_file: &String) -> Base16Colors {
-    if let "kitty" = term_input_name.as_str() {
-        kitty_colors_to_base16_colors(term_input_file)
-    } else {
-        alacritty_colors_to_base16_colors(term_input_file)
+fn create_base16_colors(term_input_name: &str, term_input_file: &str) -> Theme {
+    match term_input_name {
+        "kitty" => kitty_colors_to_base16_colors(term_input_file),
+        "xresources" => xresources_colors_to_base16_colors(term_input_file),
+        "gnome-terminal" => gnome_terminal_colors_to_base16_colors(term_input_file),
+        "konsole" => konsole_colors_to_base16_colors(term_input_file),
+        "base16" | "base24" => base16_scheme_to_base16_colors(term_input_file),
+        "vscode" => vscode_colors_to_base16_colors(term_input_file),
+        "gogh" => gogh_theme_to_base16_colors(term_input_file),
+        "neovim" | "vim" => vim_colors_to_base16_colors(term_input_file),
+        "pywal" => pywal_colors_to_base16_colors(term_input_file),
+        "terminal" => terminal_colors_to_base16_colors(term_input_file),
+        "auto" => match detect_theme_format(term_input_file) {
+            Some(term_input_name) => create_base16_colors(term_input_name, term_input_file),
+            None => panic!("Unknown theme format"),
+        },
+        _ => alacritty_colors_to_base16_colors(term_input_file),
     }
 }
 
-fn create_theme(term_output: &String, args: &Options) -> Result<(), String> {
-    let base16_colors = create_base16_colors(&args.term_input, &args.term_input_file);
-    let mut result_foot: Result<(), String> = Ok(());
-    let mut result_alacritty: Result<(), String> = Ok(());
-    let mut result_kitty: Result<(), String> = Ok(());
-    match term_output.as_str() {
-        "all" => {
-            if args.term_input != "foot" {
-                result_foot =
-                    create_foot_theme(&base16_colors, &args.foot_output_folder, &args.theme_name);
-            }
+// the colors of `ttparser generate`, from its accent or its image
+fn generate_base16_colors(args: &Options) -> Result<Theme, String> {
+    let colors = match (&args.generate_accent, &args.generate_image) {
+        (Some(accent), _) => theme_from_accent(
+            *accent,
+            args.generate_dark.unwrap_or(true),
+            args.bright_strength,
+        ),
+        (_, Some(image)) => theme_from_image(image, args.generate_dark, args.bright_strength)?,
+        _ => return Err("An accent color or an image is required".to_string()),
+    };
+    Ok(Theme {
+        colors,
+        ..Theme::default()
+    })
+}
 
-            if args.term_input != "alacritty" {
-                result_alacritty = create_alacritty_theme(
-                    &base16_colors,
-                    &args.alacritty_output_folder,
-                    &args.theme_name,
-                );
-            }
+// `theme_name`, or `theme_name-2`, `theme_name-3`... when that file was already written
+// in this run, by another theme of a folder or another format in the same folder
+fn unique_output_name(
+    written_paths: &Mutex<HashSet<PathBuf>>,
+    output_folder: &str,
+    term_output: &str,
+    theme_name: &str,
+) -> String {
+    let output_folder = fs::canonicalize(output_folder).unwrap_or(PathBuf::from(output_folder));
+    let mut written_paths = written_paths.lock().unwrap();
+    let mut output_name = theme_slug(theme_name);
+    let mut number = 2;
+    while !written_paths.insert(output_folder.join(output_file_name(term_output, &output_name))) {
+        output_name = format!("{}-{}", theme_slug(theme_name), number);
+        number += 1;
+    }
+    output_name
+}
 
-            if args.term_input != "kitty" {
-                result_kitty = create_kitty_theeme(
-                    &base16_colors,
-                    &args.kitty_output_folder,
-                    &args.theme_name,
-                );
-            }
+fn create_theme(
+    mut theme: Theme,
+    term_input: &str,
+    theme_name: &str,
+    written_paths: &Mutex<HashSet<PathBuf>>,
+    args: &Options,
+) -> Result<(String, Vec<String>), String> {
+    // what was changed or how to load the outputs, printed for a single theme only
+    let mut notes: Vec<String> = Vec::new();
+    let brights_mode = match args.generate_brights.as_str() {
+        "never" => None,
+        "identical" => Some(BrightsMode::Identical),
+        "always" => Some(BrightsMode::Always),
+        _ => Some(BrightsMode::Missing),
+    };
+    if let Some(mode) = brights_mode {
+        let mut changes = generate_brights(&mut theme.colors, args.bright_strength, mode);
+        // only Konsole has dim colors
+        if args
+            .terminal_outputs
+            .iter()
+            .any(|term_output| term_output == "konsole")
+        {
+            let always = mode == BrightsMode::Always;
+            changes.extend(generate_dims(
+                &mut theme.colors,
+                args.bright_strength,
+                always,
+            ));
         }
-        "foot" => {
-            result_foot =
-                create_foot_theme(&base16_colors, &args.foot_output_folder, &args.theme_name);
+        for change in changes {
+            notes.push(format!("Generated {}", change));
         }
-
-        "alacritty" => {
-            result_alacritty = create_alacritty_theme(
-                &base16_colors,
-                &args.alacritty_output_folder,
-                &args.theme_name,
-            );
+    }
+    // the writers need every color, a theme without them fails here instead
+    let missing = missing_colors(&theme.colors);
+    if !missing.is_empty() {
+        return Err(format!("Missing colors: {}", missing.join(", ")));
+    }
+    transform_colors(&mut theme.colors, &args.transforms, &args.transform_roles);
+    let mut theme_name = theme_name.to_string();
+    if args.opposite_variant {
+        theme.colors = opposite_variant(&theme.colors);
+        let background = Rgb::from_hex(theme.colors.get("background").unwrap()).unwrap();
+        let variant = match background.luminance() < 0.5 {
+            true => "dark",
+            false => "light",
+        };
+        theme_name = format!("{}-{}", theme_name, variant);
+        if let Some(name) = theme.metadata.name.as_mut() {
+            *name = format!("{} ({})", name, variant);
         }
-        "kitty" => {
-            result_kitty =
-                create_kitty_theeme(&base16_colors, &args.kitty_output_folder, &args.theme_name);
+        theme.metadata.variant = Some(variant.to_string());
+    }
+    if let Some(deficiency) = args.daltonize {
+        daltonize_colors(&mut theme.colors, deficiency);
+    }
+    // contrast is fixed against the background the theme ends up with
+    if let Some(target) = args.fix_contrast {
+        for change in fix_contrast(&mut theme.colors, target) {
+            notes.push(format!("Contrast fixed {}", change));
+        }
+    }
+    if let Some(deficiency) = args.simulate {
+        simulate_colors(&mut theme.colors, deficiency);
+    }
+    let mut errors: Vec<String> = Vec::new();
+    for term_output in args.terminal_outputs.iter() {
+        if args.skip_input_format && term_output == term_input {
+            continue;
+        }
+        let output_folder = &args.output_folders[term_output];
+        if fs::create_dir_all(output_folder).is_err() {
+            errors.push(format!("{}: Error creating output folder", term_output));
+            continue;
+        }
+        let theme_name = unique_output_name(written_paths, output_folder, term_output, &theme_name);
+        let result = match term_output.as_str() {
+            "foot" => create_foot_theme(&theme, output_folder, &theme_name),
+            "alacritty" => create_alacritty_theme(&theme, output_folder, &theme_name),
+            "kitty" => create_kitty_theeme(&theme, output_folder, &theme_name),
+            "xresources" => create_xresources_theme(&theme, output_folder, &theme_name),
+            "gnome-terminal" => create_gnome_terminal_theme(&theme, output_folder, &theme_name),
+            "konsole" => create_konsole_theme(&theme, output_folder, &theme_name),
+            "base16" => create_base16_theme(&theme, output_folder, &theme_name),
+            "base24" => create_base24_theme(&theme, output_folder, &theme_name),
+            "gogh" => create_gogh_theme(&theme, output_folder, &theme_name),
+            "vscode" => create_vscode_theme(&theme, output_folder, &theme_name),
+            "neovim" => create_neovim_theme(&theme, output_folder, &theme_name),
+            "vim" => create_vim_theme(&theme, output_folder, &theme_name),
+            "vt" => create_vt_theme(&theme, output_folder, &theme_name),
+            "svg" => create_svg_theme(&theme, output_folder, &theme_name),
+            "html" => create_html_theme(&theme, output_folder, &theme_name),
+            "png" => create_png_theme(&theme, output_folder, &theme_name),
+            _ => Err(format!("Unknown output {}", term_output)),
+        };
+        // one failing output does not stop the others
+        match (result, term_output.as_str()) {
+            (Err(err), _) => errors.push(format!("{}: {}", term_output, err)),
+            (Ok(_), "gnome-terminal") => notes.push(format!(
+                "Load it with: dconf load /org/gnome/terminal/legacy/profiles:/:<profile-uuid>/ < \"{}/{}\"",
+                output_folder,
+                output_file_name(term_output, &theme_name)
+            )),
+            (Ok(_), "vt") => notes.push(format!(
+                "Apply it with: setvtrgb \"{}/{}\"",
+                output_folder,
+                output_file_name(term_output, &theme_name)
+            )),
+            (Ok(_), _) => {}
         }
-        &_ => {}
+        // the kernel command line is a file of its own next to the vt palette
+        if term_output == "vt" && args.vt_kernel_cmdline {
+            let cmdline_name =
+                unique_output_name(written_paths, output_folder, "vt-cmdline", &theme_name);
+            match create_vt_kernel_cmdline(&theme, output_folder, &cmdline_name) {
+                Err(err) => errors.push(format!("vt kernel command line: {}", err)),
+                Ok(_) => notes.push(format!(
+                    "Boot with it: append the line of \"{}/{}\" to the kernel command line",
+                    output_folder,
+                    output_file_name("vt-cmdline", &cmdline_name)
+                )),
+            }
+        }
+    }
+    // the name the outputs were written under, with the suffix of --opposite-variant
+    match errors.is_empty() {
+        true => Ok((theme_name, notes)),
+        false => Err(errors.join("\n")),
+    }
+}
+
+fn apply_theme(args: &Options) {
+    let sequences = match args.reset {
+        true => osc_reset_sequences(),
+        false => osc_theme_sequences(
+            &create_base16_colors(&args.term_input, &args.term_input_file).colors,
+        ),
+    };
+    match args.script {
+        true => print!("{}", osc_shell_script(&sequences)),
+        false => print!("{}", sequences.concat()),
     }
+}
 
-    //  TODO: this need a refector
-    match (result_foot, result_alacritty, result_kitty) {
-        (Ok(()), Ok(()), Ok(())) => Ok(()),
-        (Err(err_foot), _, _) => Err(err_foot),
-        (_, Err(err_alacritty), _) => Err(err_alacritty),
-        (_, _, Err(err_kitty)) => Err(err_kitty),
+// prints every pair and whether it meets its minimum, true when all of them do
+fn check_contrast(args: &Options) -> bool {
+    let base16_colors = create_base16_colors(&args.term_input, &args.term_input_file).colors;
+    let missing = missing_colors(&base16_colors);
+    if !missing.is_empty() {
+        eprintln!("Missing colors: {}", missing.join(", "));
+        return false;
+    }
+    let mut simulated_colors = base16_colors.clone();
+    if let Some(deficiency) = args.simulate {
+        simulate_colors(&mut simulated_colors, deficiency);
+    }
+    let checks = audit_contrast(&simulated_colors, &args.contrast_thresholds, args.apca);
+    for check in checks.iter() {
+        println!(
+            "{} {:<46} #{} on #{} {:>6.2} (min {:.2})",
+            if check.passed() { "PASS" } else { "FAIL" },
+            check.label,
+            check.foreground.to_hex(),
+            check.background.to_hex(),
+            check.contrast,
+            check.minimum
+        );
     }
+    let failures = checks.iter().filter(|check| !check.passed()).count();
+    println!("{} of {} pairs below the minimum", failures, checks.len());
+    if !args.cvd {
+        return failures == 0;
+    }
+    // every deficiency is simulated on the theme itself, not on top of --simulate
+    let collisions = cvd_collisions(&base16_colors, args.min_cvd_distance);
+    for collision in collisions.iter() {
+        println!(
+            "FAIL {:<46} distance {:.3} (min {:.3})",
+            format!(
+                "{} and {} with {}",
+                collision.first,
+                collision.second,
+                collision.deficiency.name()
+            ),
+            collision.distance,
+            args.min_cvd_distance
+        );
+    }
+    println!(
+        "{} ANSI color pairs collide with a color vision deficiency",
+        collisions.len()
+    );
+    failures == 0 && collisions.is_empty()
+}
+
+// converts every theme of the folder or glob, named after its file, and prints a summary
+fn create_batch_themes(theme_paths: &[PathBuf], args: &Options) -> bool {
+    let theme_names: HashMap<&Path, String> = theme_paths
+        .iter()
+        .map(PathBuf::as_path)
+        .zip(batch_theme_names(theme_paths))
+        .collect();
+    let written_paths: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+    let results = run_batch(theme_paths, |theme_path| {
+        let theme_name = &theme_names[theme_path];
+        let theme_path = theme_path.to_string_lossy();
+        let term_input = match args.term_input.as_str() {
+            "auto" => detect_theme_format(&theme_path).ok_or("Unknown theme format")?,
+            term_input => term_input,
+        };
+        let mut theme = create_base16_colors(term_input, &theme_path);
+        // themes without a name inside take the one of their file
+        theme.metadata.name.get_or_insert(
+            Path::new(theme_path.as_ref())
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+        let (theme_name, _) = create_theme(theme, term_input, theme_name, &written_paths, args)?;
+        Ok(theme_name)
+    });
+    for (theme_path, result) in theme_paths.iter().zip(results.iter()) {
+        match result {
+            Ok(theme_name) => println!("OK   {} -> {}", theme_path.display(), theme_name),
+            Err(err) => println!("FAIL {}: {}", theme_path.display(), err),
+        }
+    }
+    let failures = results.iter().filter(|result| result.is_err()).count();
+    println!(
+        "{} themes converted, {} failed",
+        results.len() - failures,
+        failures
+    );
+    failures == 0
 }
 
 fn main() {
     let args: Options = parse_args();
-    println!("{:#?}", args);
 
-    let result_create_theme = create_theme(&args.terminal_output, &args);
-    let _ = match result_create_theme {
-        Err(err) => Err(err),
-        Ok(_) => {
-            println!("Theme {} parsed", args.theme_name);
-            Ok(())
+    match args.command.as_str() {
+        "apply" => return apply_theme(&args),
+        "check" => {
+            if !check_contrast(&args) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        "preview" => {
+            let mut base16_colors =
+                create_base16_colors(&args.term_input, &args.term_input_file).colors;
+            if let Some(deficiency) = args.simulate {
+                simulate_colors(&mut base16_colors, deficiency);
+            }
+            match terminal_preview(&base16_colors) {
+                Ok(preview) => print!("{}", preview),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    if args.command == "convert" {
+        match batch_input_paths(&args.term_input_file) {
+            Some(Ok(theme_paths)) => {
+                if !create_batch_themes(&theme_paths, &args) {
+                    std::process::exit(1);
+                }
+                return;
+            }
+            Some(Err(err)) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            None => {}
         }
+    }
+
+    let theme = match args.command.as_str() {
+        "generate" => generate_base16_colors(&args),
+        _ => Ok(create_base16_colors(
+            &args.term_input,
+            &args.term_input_file,
+        )),
     };
+    let result_create_theme = theme.and_then(|mut theme| {
+        // -n renames the theme, otherwise it keeps the name found in it
+        if args.explicit_theme_name {
+            theme.metadata.name = Some(args.theme_name.clone());
+        }
+        let theme_name = theme_slug(theme.metadata.name.get_or_insert(args.theme_name.clone()));
+        let written_paths = Mutex::new(HashSet::new());
+        create_theme(theme, &args.term_input, &theme_name, &written_paths, &args)
+    });
+    match result_create_theme {
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        Ok((theme_name, notes)) => {
+            for note in notes {
+                println!("{}", note);
+            }
+            println!("Theme {} parsed", theme_name);
+        }
+    }
 }
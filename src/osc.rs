@@ -0,0 +1,205 @@
+use crate::color::Rgb;
+use crate::theme_parser::Base16Colors;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant};
+
+const ESC: &str = "\x1b";
+// string terminator, understood by every terminal that understands OSC
+const ST: &str = "\x1b\\";
+
+fn osc(command: &str) -> String {
+    format!("{}]{}{}", ESC, command, ST)
+}
+
+/// OSC 4 for the 16 colors, OSC 10/11/12 for foreground/background/cursor
+/// and OSC 17/19 for the selection when the theme has one.
+pub fn osc_theme_sequences(base16_colors: &Base16Colors) -> Vec<String> {
+    let color = |color_name: &str| {
+        base16_colors
+            .get(color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+            .map(Rgb::to_x11)
+    };
+    let mut sequences: Vec<String> = (0..16)
+        .filter_map(|number| {
+            color(&format!("color{}", number)).map(|spec| osc(&format!("4;{};{}", number, spec)))
+        })
+        .collect();
+    for (code, color_name) in [
+        (10, "foreground"),
+        (11, "background"),
+        (12, "cursor"),
+        (17, "selection_background"),
+        (19, "selection_foreground"),
+    ] {
+        if let Some(spec) = color(color_name) {
+            sequences.push(osc(&format!("{};{}", code, spec)));
+        }
+    }
+    sequences
+}
+
+/// Puts the palette, foreground, background, cursor and selection back to the terminal defaults.
+pub fn osc_reset_sequences() -> Vec<String> {
+    ["104", "110", "111", "112", "117", "119"]
+        .iter()
+        .map(|command| osc(command))
+        .collect()
+}
+
+/// A POSIX shell script with one `printf` per sequence.
+pub fn osc_shell_script(sequences: &[String]) -> String {
+    let printf_lines = sequences
+        .iter()
+        .map(|sequence| {
+            format!(
+                "printf '{}'",
+                sequence.replace('\\', "\\\\").replace(ESC, "\\033")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("#!/bin/sh\n{}\n", printf_lines)
+}
+
+// puts the tty in non canonical mode without echo, so replies can be read byte by byte
+// without showing up on screen, and hands back the previous settings
+fn enter_raw_mode(fd: i32) -> Option<libc::termios> {
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return None;
+        }
+        let original = termios;
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+        termios.c_cc[libc::VMIN] = 0;
+        termios.c_cc[libc::VTIME] = 0;
+        libc::tcsetattr(fd, libc::TCSANOW, &termios);
+        Some(original)
+    }
+}
+
+fn wait_readable(fd: i32, timeout: Duration) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut pollfd, 1, timeout.as_millis() as libc::c_int) > 0 }
+}
+
+/// Asks the terminal behind `tty` for its palette (OSC 4), foreground, background
+/// and cursor (OSC 10/11/12) and parses the `rgb:rrrr/gggg/bbbb` replies.
+///
+/// A primary device attributes request goes last: every terminal answers it, so once
+/// its reply arrives there is nothing left to wait for, even from terminals that
+/// ignore some of the color queries. Otherwise reading stops after `timeout`.
+pub fn osc_query_colors<T: Read + Write + AsRawFd>(tty: &mut T, timeout: Duration) -> Base16Colors {
+    let fd = tty.as_raw_fd();
+    let original = enter_raw_mode(fd);
+
+    let mut queries: Vec<String> = (0..16)
+        .map(|number| osc(&format!("4;{};?", number)))
+        .collect();
+    queries.extend(["10;?", "11;?", "12;?"].iter().map(|command| osc(command)));
+    queries.push(format!("{}[c", ESC));
+    let _ = tty.write_all(queries.concat().as_bytes());
+    let _ = tty.flush();
+
+    let device_attributes = Regex::new(r"\x1b\[\?[0-9;]*c").unwrap();
+    let deadline = Instant::now() + timeout;
+    let mut replies: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        if !wait_readable(fd, remaining) {
+            break;
+        }
+        match tty.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => replies.extend_from_slice(&buffer[..read]),
+        }
+        if device_attributes.is_match(&String::from_utf8_lossy(&replies)) {
+            break;
+        }
+    }
+
+    if let Some(original) = original {
+        unsafe {
+            libc::tcsetattr(fd, libc::TCSANOW, &original);
+        }
+    }
+
+    // replies end with either BEL or ST, and echo the palette index for OSC 4
+    let reply =
+        Regex::new(r"\x1b\](\d+);(?:(\d+);)?(rgb:[0-9A-Fa-f]+/[0-9A-Fa-f]+/[0-9A-Fa-f]+)").unwrap();
+    let mut base16_colors: Base16Colors = HashMap::new();
+    for captures in reply.captures_iter(&String::from_utf8_lossy(&replies)) {
+        let color_name = match (&captures[1], captures.get(2)) {
+            ("4", Some(number)) => format!("color{}", number.as_str()),
+            ("10", None) => "foreground".to_string(),
+            ("11", None) => "background".to_string(),
+            ("12", None) => "cursor".to_string(),
+            _ => continue,
+        };
+        if let Some(color) = Rgb::from_x11(&captures[3]) {
+            base16_colors.insert(color_name, color.to_hex());
+        }
+    }
+    base16_colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::os::fd::FromRawFd;
+    use std::thread;
+
+    #[test]
+    fn osc_query_colors_reads_pty_replies() {
+        let (mut master, mut slave) = (0, 0);
+        let opened = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(opened, 0);
+        let mut terminal = unsafe { File::from_raw_fd(master) };
+        let mut tty = unsafe { File::from_raw_fd(slave) };
+
+        // the terminal side answers once every query, down to DA1, has arrived
+        let answering = thread::spawn(move || {
+            let mut queries: Vec<u8> = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !String::from_utf8_lossy(&queries).contains("\x1b[c") {
+                let read = terminal.read(&mut buffer).unwrap();
+                queries.extend_from_slice(&buffer[..read]);
+            }
+            let replies = [
+                "\x1b]4;0;rgb:1d1d/2020/2f2f\x07",
+                "\x1b]4;1;rgb:f7f7/7676/8e8e\x1b\\",
+                "\x1b]10;rgb:c0c0/caca/f5f5\x1b\\",
+                "\x1b]11;rgb:2424/2828/3b3b\x07",
+                "\x1b[?62;22c",
+            ];
+            terminal.write_all(replies.concat().as_bytes()).unwrap();
+            // keeps the master open until the replies are read
+            terminal
+        });
+
+        let base16_colors = osc_query_colors(&mut tty, Duration::from_secs(5));
+        drop(answering.join().unwrap());
+        assert_eq!(base16_colors.get("color0").unwrap(), "1d202f");
+        assert_eq!(base16_colors.get("color1").unwrap(), "f7768e");
+        assert_eq!(base16_colors.get("foreground").unwrap(), "c0caf5");
+        assert_eq!(base16_colors.get("background").unwrap(), "24283b");
+        assert_eq!(base16_colors.len(), 4);
+    }
+}
@@ -0,0 +1,499 @@
+use crate::color::Rgb;
+use crate::theme_parser::Base16Colors;
+use font8x8::{UnicodeFonts, BASIC_FONTS};
+
+const PREVIEW_WIDTH: usize = 64;
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// a run of text drawn with the same foreground, background and weight
+struct Segment {
+    text: String,
+    fg: Rgb,
+    bg: Rgb,
+    bold: bool,
+}
+
+/// The colors a preview needs, with the usual terminal fallbacks for the optional ones
+/// and the normal colors for missing brights.
+pub struct PreviewColors {
+    pub background: Rgb,
+    pub foreground: Rgb,
+    pub cursor: Rgb,
+    pub cursor_fg: Rgb,
+    pub selection_background: Rgb,
+    pub selection_foreground: Rgb,
+    pub palette: Vec<Rgb>,
+}
+
+impl PreviewColors {
+    pub fn from_base16_colors(base16_colors: &Base16Colors) -> Result<PreviewColors, String> {
+        let color = |color_name: &str| {
+            base16_colors
+                .get(color_name)
+                .and_then(|color_hex| Rgb::from_hex(color_hex))
+        };
+        let required =
+            |color_name: &str| color(color_name).ok_or(format!("Missing color {}", color_name));
+        let background = required("background")?;
+        let foreground = required("foreground")?;
+        let mut palette: Vec<Rgb> = Vec::new();
+        for number in 0..16 {
+            let color_name = format!("color{}", number);
+            match (color(&color_name), number >= 8) {
+                (Some(color), _) => palette.push(color),
+                (None, true) => palette.push(palette[number - 8]),
+                (None, false) => return Err(format!("Missing color {}", color_name)),
+            }
+        }
+        Ok(PreviewColors {
+            background,
+            foreground,
+            cursor: color("cursor").unwrap_or(foreground),
+            cursor_fg: color("cursor_fg").unwrap_or(background),
+            selection_background: color("selection_background")
+                .unwrap_or(background.mix(foreground, 0.25)),
+            selection_foreground: color("selection_foreground").unwrap_or(foreground),
+            palette,
+        })
+    }
+
+    fn segment(&self, text: &str, fg: Rgb) -> Segment {
+        Segment {
+            text: text.to_string(),
+            fg,
+            bg: self.background,
+            bold: false,
+        }
+    }
+
+    fn bold(&self, text: &str, fg: Rgb) -> Segment {
+        Segment {
+            bold: true,
+            ..self.segment(text, fg)
+        }
+    }
+}
+
+fn sgr_line(colors: &PreviewColors, mut segments: Vec<Segment>) -> String {
+    // pads with the theme background so the preview is a solid block
+    let width: usize = segments
+        .iter()
+        .map(|segment| segment.text.chars().count())
+        .sum();
+    segments.push(colors.segment(
+        &" ".repeat(PREVIEW_WIDTH.saturating_sub(width)),
+        colors.foreground,
+    ));
+    segments
+        .iter()
+        .map(|segment| {
+            format!(
+                "\x1b[{}38;2;{};{};{};48;2;{};{};{}m{}\x1b[0m",
+                if segment.bold { "1;" } else { "" },
+                segment.fg.r,
+                segment.fg.g,
+                segment.fg.b,
+                segment.bg.r,
+                segment.bg.g,
+                segment.bg.b,
+                segment.text
+            )
+        })
+        .collect()
+}
+
+/// Lines of sample content shared by every preview: the palette as swatches and
+/// as text, cursor and selection, and `ls`/`git diff` style output.
+fn preview_lines(colors: &PreviewColors) -> Vec<Vec<Segment>> {
+    let palette = &colors.palette;
+    let mut lines: Vec<Vec<Segment>> = vec![vec![]];
+
+    for (label, offset) in [(" normal ", 0), (" bright ", 8)] {
+        let mut swatches = vec![colors.segment(label, colors.foreground)];
+        for number in offset..offset + 8 {
+            swatches.push(Segment {
+                text: format!(" {:>2} ", number),
+                fg: match palette[number].luminance() > 0.4 {
+                    true => palette[0],
+                    false => palette[15],
+                },
+                bg: palette[number],
+                bold: false,
+            });
+            swatches.push(colors.segment(" ", colors.foreground));
+        }
+        lines.push(swatches);
+    }
+    lines.push(vec![]);
+
+    for offset in [0, 8] {
+        let mut names = vec![colors.segment(" ", colors.foreground)];
+        for (number, name) in COLOR_NAMES.iter().enumerate() {
+            names.push(colors.segment(name, palette[number + offset]));
+            names.push(colors.segment(" ", colors.foreground));
+        }
+        lines.push(names);
+    }
+    lines.push(vec![
+        colors.segment(" foreground ", colors.foreground),
+        colors.bold("bold", colors.foreground),
+        colors.segment(" ", colors.foreground),
+        Segment {
+            text: " reverse ".to_string(),
+            fg: colors.background,
+            bg: colors.foreground,
+            bold: false,
+        },
+    ]);
+    lines.push(vec![]);
+
+    lines.push(vec![
+        colors.segment(" cursor ", colors.foreground),
+        Segment {
+            text: "a".to_string(),
+            fg: colors.cursor_fg,
+            bg: colors.cursor,
+            bold: false,
+        },
+        colors.segment("  selection ", colors.foreground),
+        Segment {
+            text: " selected text ".to_string(),
+            fg: colors.selection_foreground,
+            bg: colors.selection_background,
+            bold: false,
+        },
+    ]);
+    lines.push(vec![]);
+
+    lines.push(vec![
+        colors.segment(" $ ", palette[2]),
+        colors.segment("ls --color", colors.foreground),
+    ]);
+    lines.push(vec![
+        colors.segment(" Cargo.toml  ", colors.foreground),
+        colors.bold("src/", palette[4]),
+        colors.segment("  ", colors.foreground),
+        colors.bold("build.sh*", palette[2]),
+        colors.segment("  ", colors.foreground),
+        colors.bold("latest@", palette[6]),
+        colors.segment("  ", colors.foreground),
+        colors.bold("theme.tar.gz", palette[1]),
+    ]);
+    lines.push(vec![
+        colors.segment(" $ ", palette[2]),
+        colors.segment("git diff", colors.foreground),
+    ]);
+    lines.push(vec![
+        colors.bold(" diff --git a/theme.conf b/theme.conf", colors.foreground)
+    ]);
+    lines.push(vec![colors.segment(" @@ -1,2 +1,2 @@", palette[6])]);
+    lines.push(vec![colors.segment(" -background #000000", palette[1])]);
+    lines.push(vec![colors.segment(" +background #1d1f21", palette[2])]);
+    lines.push(vec![
+        colors.segment("  foreground #c5c8c6", colors.foreground)
+    ]);
+    lines.push(vec![]);
+    lines
+}
+
+/// Renders the theme with 24-bit SGR sequences, independently of the running terminal palette.
+pub fn terminal_preview(base16_colors: &Base16Colors) -> Result<String, String> {
+    let colors = PreviewColors::from_base16_colors(base16_colors)?;
+    Ok(preview_lines(&colors)
+        .into_iter()
+        .map(|segments| sgr_line(&colors, segments) + "\n")
+        .collect())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// the palette swatches with their hex codes, labelled the way the rest of ttparser names them
+fn palette_entries(colors: &PreviewColors) -> Vec<(String, Rgb)> {
+    let mut entries: Vec<(String, Rgb)> = vec![
+        ("background".to_string(), colors.background),
+        ("foreground".to_string(), colors.foreground),
+        ("cursor".to_string(), colors.cursor),
+        ("selection".to_string(), colors.selection_background),
+    ];
+    for (number, color) in colors.palette.iter().enumerate() {
+        entries.push((format!("color{}", number), *color));
+    }
+    entries
+}
+
+const SVG_CELL_WIDTH: f64 = 8.4;
+const SVG_CELL_HEIGHT: f64 = 18.0;
+const SVG_SWATCH_WIDTH: f64 = 64.0;
+const SVG_PADDING: f64 = 16.0;
+
+/// A self-contained SVG with the palette, its hex codes and a mock terminal session.
+pub fn svg_preview(base16_colors: &Base16Colors, theme_name: &str) -> Result<String, String> {
+    let colors = PreviewColors::from_base16_colors(base16_colors)?;
+    let entries = palette_entries(&colors);
+    let columns = 8;
+    let width = SVG_PADDING * 2.0 + PREVIEW_WIDTH as f64 * SVG_CELL_WIDTH;
+    let swatch_step = (width - SVG_PADDING * 2.0) / columns as f64;
+    let palette_rows = entries.len().div_ceil(columns);
+    let session_top = SVG_PADDING * 2.0 + 24.0 + palette_rows as f64 * 60.0;
+    let lines = preview_lines(&colors);
+    let height = session_top + lines.len() as f64 * SVG_CELL_HEIGHT + SVG_PADDING;
+
+    let mut svg = vec![
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"14\">",
+            width, height
+        ),
+        format!(
+            "<rect width=\"100%\" height=\"100%\" fill=\"#{}\"/>",
+            colors.background.to_hex()
+        ),
+        format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"#{}\" font-weight=\"bold\">{}</text>",
+            SVG_PADDING,
+            SVG_PADDING + 14.0,
+            colors.foreground.to_hex(),
+            xml_escape(theme_name)
+        ),
+    ];
+
+    for (index, (label, color)) in entries.iter().enumerate() {
+        let x = SVG_PADDING + (index % columns) as f64 * swatch_step;
+        let y = SVG_PADDING + 24.0 + (index / columns) as f64 * 60.0;
+        svg.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"28\" rx=\"4\" fill=\"#{}\" stroke=\"#{}\"/>",
+            x,
+            y,
+            SVG_SWATCH_WIDTH,
+            color.to_hex(),
+            colors.foreground.to_hex()
+        ));
+        svg.push(format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"#{}\" font-size=\"10\">{}</text>",
+            x,
+            y + 40.0,
+            colors.foreground.to_hex(),
+            label
+        ));
+        svg.push(format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"#{}\" font-size=\"10\">#{}</text>",
+            x,
+            y + 52.0,
+            colors.foreground.to_hex(),
+            color.to_hex()
+        ));
+    }
+
+    for (row, segments) in lines.iter().enumerate() {
+        let y = session_top + row as f64 * SVG_CELL_HEIGHT;
+        let mut column = 0;
+        for segment in segments {
+            let x = SVG_PADDING + column as f64 * SVG_CELL_WIDTH;
+            let length = segment.text.chars().count();
+            if segment.bg != colors.background {
+                svg.push(format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{}\"/>",
+                    x,
+                    y,
+                    length as f64 * SVG_CELL_WIDTH,
+                    SVG_CELL_HEIGHT,
+                    segment.bg.to_hex()
+                ));
+            }
+            if !segment.text.trim().is_empty() {
+                svg.push(format!(
+                    "<text x=\"{}\" y=\"{}\" fill=\"#{}\"{} xml:space=\"preserve\">{}</text>",
+                    x,
+                    y + 13.0,
+                    segment.fg.to_hex(),
+                    if segment.bold {
+                        " font-weight=\"bold\""
+                    } else {
+                        ""
+                    },
+                    xml_escape(&segment.text)
+                ));
+            }
+            column += length;
+        }
+    }
+    svg.push("</svg>".to_string());
+    Ok(svg.join("\n") + "\n")
+}
+
+/// A self-contained HTML page with the palette, its hex codes and a mock terminal session.
+pub fn html_preview(base16_colors: &Base16Colors, theme_name: &str) -> Result<String, String> {
+    let colors = PreviewColors::from_base16_colors(base16_colors)?;
+    let swatches = palette_entries(&colors)
+        .iter()
+        .map(|(label, color)| {
+            format!(
+                "<figure><div style=\"background:#{0}\"></div><figcaption>{1}<br>#{0}</figcaption></figure>",
+                color.to_hex(),
+                label
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let session = preview_lines(&colors)
+        .iter()
+        .map(|segments| {
+            segments
+                .iter()
+                .map(|segment| {
+                    format!(
+                        "<span style=\"color:#{};background:#{}{}\">{}</span>",
+                        segment.fg.to_hex(),
+                        segment.bg.to_hex(),
+                        if segment.bold {
+                            ";font-weight:bold"
+                        } else {
+                            ""
+                        },
+                        xml_escape(&segment.text)
+                    )
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Ok(format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{0}</title>
+<style>
+body {{ background: #{1}; color: #{2}; font-family: monospace; margin: 2em; }}
+.palette {{ display: grid; grid-template-columns: repeat(8, 6em); gap: 0.5em; }}
+figure {{ margin: 0; font-size: 0.75em; }}
+figure div {{ height: 2.5em; border: 1px solid #{2}; border-radius: 4px; }}
+pre {{ font-size: 14px; line-height: 18px; }}
+</style>
+</head>
+<body>
+<h1>{0}</h1>
+<div class=\"palette\">
+{3}
+</div>
+<pre>
+{4}
+</pre>
+</body>
+</html>
+",
+        xml_escape(theme_name),
+        colors.background.to_hex(),
+        colors.foreground.to_hex(),
+        swatches,
+        session
+    ))
+}
+
+const PNG_SCALE: usize = 2;
+const PNG_CELL_WIDTH: usize = 8 * PNG_SCALE;
+const PNG_CELL_HEIGHT: usize = 10 * PNG_SCALE;
+const PNG_PADDING: usize = 16;
+const PNG_BAR_HEIGHT: usize = 24;
+
+// an RGB framebuffer the mock session gets drawn into
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, fill: Rgb) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: [fill.r, fill.g, fill.b].repeat(width * height),
+        }
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let offset = (row * self.width + column) * 3;
+                self.pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+    }
+
+    // 8x8 glyphs from font8x8, scaled and vertically centered in the cell, bold drawn twice
+    fn draw_char(&mut self, x: usize, y: usize, c: char, color: Rgb, bold: bool) {
+        let glyph = BASIC_FONTS.get(c).unwrap_or([0; 8]);
+        let top = y + (PNG_CELL_HEIGHT - 8 * PNG_SCALE) / 2;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..8 {
+                if bits & (1 << column) == 0 {
+                    continue;
+                }
+                let pixel_x = x + column * PNG_SCALE;
+                let pixel_y = top + row * PNG_SCALE;
+                self.fill_rect(pixel_x, pixel_y, PNG_SCALE, PNG_SCALE, color);
+                if bold {
+                    self.fill_rect(pixel_x + 1, pixel_y, PNG_SCALE, PNG_SCALE, color);
+                }
+            }
+        }
+    }
+}
+
+/// A fixed size PNG of the mock terminal session with a 16 color bar underneath,
+/// rendered on the CPU with an embedded bitmap font.
+pub fn png_preview(base16_colors: &Base16Colors) -> Result<Vec<u8>, String> {
+    let colors = PreviewColors::from_base16_colors(base16_colors)?;
+    let lines = preview_lines(&colors);
+    let width = PNG_PADDING * 2 + PREVIEW_WIDTH * PNG_CELL_WIDTH;
+    let height = PNG_PADDING * 3 + lines.len() * PNG_CELL_HEIGHT + PNG_BAR_HEIGHT;
+    let mut canvas = Canvas::new(width, height, colors.background);
+
+    for (row, segments) in lines.iter().enumerate() {
+        let y = PNG_PADDING + row * PNG_CELL_HEIGHT;
+        let mut column = 0;
+        for segment in segments {
+            for c in segment.text.chars() {
+                let x = PNG_PADDING + column * PNG_CELL_WIDTH;
+                canvas.fill_rect(x, y, PNG_CELL_WIDTH, PNG_CELL_HEIGHT, segment.bg);
+                canvas.draw_char(x, y, c, segment.fg, segment.bold);
+                column += 1;
+            }
+        }
+    }
+
+    let bar_top = PNG_PADDING * 2 + lines.len() * PNG_CELL_HEIGHT;
+    let bar_width = (width - PNG_PADDING * 2) / colors.palette.len();
+    for (number, color) in colors.palette.iter().enumerate() {
+        canvas.fill_rect(
+            PNG_PADDING + number * bar_width,
+            bar_top,
+            bar_width,
+            PNG_BAR_HEIGHT,
+            *color,
+        );
+    }
+
+    let mut png_bytes: Vec<u8> = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|err| format!("Error encoding png: {}", err))?;
+    writer
+        .write_image_data(&canvas.pixels)
+        .map_err(|err| format!("Error encoding png: {}", err))?;
+    writer
+        .finish()
+        .map_err(|err| format!("Error encoding png: {}", err))?;
+    Ok(png_bytes)
+}
@@ -1,16 +1,149 @@
-use crate::theme_parser::Base16Colors;
+use crate::color::Rgb;
+use crate::preview::{html_preview, png_preview, svg_preview};
+use crate::theme_parser::{Base16Colors, Theme, ThemeMetadata};
 use std::fs;
 
+/// Every format ttparser writes, in the order `-o all` writes them.
+pub const OUTPUT_FORMATS: [&str; 16] = [
+    "foot",
+    "alacritty",
+    "kitty",
+    "xresources",
+    "gnome-terminal",
+    "konsole",
+    "base16",
+    "base24",
+    "gogh",
+    "vscode",
+    "neovim",
+    "vim",
+    "vt",
+    "svg",
+    "html",
+    "png",
+];
+
+// the Latin letters an accented letter is written with in a file name
+fn transliterate(character: char) -> Option<&'static str> {
+    let latin = match character {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' | 'ă' => "a",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'ł' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'ť' | 'ț' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'þ' => "th",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// The file name of a theme: lowercase letters and digits, accents transliterated
+/// ("Rosé Pine" is `rose-pine`) and other scripts kept as they are, every other run of
+/// characters turned into a single `-` ("Tokyo Night / Storm" is `tokyo-night-storm`).
+pub fn theme_slug(theme_name: &str) -> String {
+    let mut slug = String::new();
+    for character in theme_name.to_lowercase().chars() {
+        match transliterate(character) {
+            Some(latin) => slug.push_str(latin),
+            None if character.is_alphanumeric() => slug.push(character),
+            None if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            None => {}
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    match slug.is_empty() {
+        true => "theme".to_string(),
+        false => slug.to_string(),
+    }
+}
+
+/// The file `term_output` writes for `theme_name`, every format with its own extension
+/// so that they can share a folder.
+pub fn output_file_name(term_output: &str, theme_name: &str) -> String {
+    let extension = match term_output {
+        "alacritty" => ".yml",
+        "kitty" => ".conf",
+        "xresources" => ".Xresources",
+        "gnome-terminal" => ".dconf",
+        "konsole" => ".colorscheme",
+        "base16" => ".yaml",
+        "base24" => ".base24.yaml",
+        "gogh" => ".gogh.yml",
+        "vscode" => ".json",
+        "neovim" => ".lua",
+        "vim" => ".vim",
+        "vt" => ".vtrgb",
+        "vt-cmdline" => ".cmdline",
+        "svg" => ".svg",
+        "html" => ".html",
+        "png" => ".png",
+        _ => "",
+    };
+    format!("{}{}", theme_slug(theme_name), extension)
+}
+
+// the name of the theme from its metadata, `theme_name` when it has none
+fn display_name<'a>(metadata: &'a ThemeMetadata, theme_name: &'a str) -> &'a str {
+    metadata.name.as_deref().unwrap_or(theme_name)
+}
+
+// license and upstream as comment lines, for the formats with their own name and author
+fn license_comments(metadata: &ThemeMetadata, comment: &str) -> String {
+    [
+        ("license", &metadata.license),
+        ("upstream", &metadata.upstream),
+    ]
+    .iter()
+    .filter_map(|(key, value)| {
+        value
+            .as_ref()
+            .map(|value| format!("{} {}: {}\n", comment, key, value))
+    })
+    .collect()
+}
+
+// name, author, license and upstream as comment lines, the way kitty themes carry them
+fn metadata_comments(metadata: &ThemeMetadata, theme_name: &str, comment: &str) -> String {
+    let mut lines = vec![format!(
+        "{} name: {}",
+        comment,
+        display_name(metadata, theme_name)
+    )];
+    for (key, value) in [
+        ("author", &metadata.author),
+        ("license", &metadata.license),
+        ("upstream", &metadata.upstream),
+    ] {
+        if let Some(value) = value {
+            lines.push(format!("{} {}: {}", comment, key, value));
+        }
+    }
+    lines.join("\n")
+}
+
 pub fn create_foot_theme(
-    base16_colors: &Base16Colors,
+    theme: &Theme,
     foot_path: &String,
-    theme_name: &String,
+    theme_name: &str,
 ) -> Result<(), String> {
-    println!("base16_colors: from foot_theme {:#?}", base16_colors);
+    let base16_colors = &theme.colors;
     let file_conf_str = format!(
         "
     # -*- conf -*-
-    # {:}
+{}
     [cursor]
     color={} {}
 
@@ -38,7 +171,7 @@ pub fn create_foot_theme(
     bright6={} # cyan
     bright7={} # white
     ",
-        theme_name,
+        metadata_comments(&theme.metadata, theme_name, "    #"),
         base16_colors
             .get("cursor_fg")
             .unwrap_or(&"000000".to_string()),
@@ -63,9 +196,8 @@ pub fn create_foot_theme(
         base16_colors.get("color15").unwrap(),
     );
 
-    let write_path = format!("{}/{}", foot_path, theme_name.to_lowercase());
+    let write_path = format!("{}/{}", foot_path, output_file_name("foot", theme_name));
 
-    println!("{}", file_conf_str);
     let result = fs::write(write_path, file_conf_str.trim());
     match result {
         Err(_) => Err("Error writing file".to_string()),
@@ -74,13 +206,14 @@ pub fn create_foot_theme(
 }
 
 pub fn create_alacritty_theme(
-    base16_colors: &Base16Colors,
+    theme: &Theme,
     alacritty_path: &String,
-    theme_name: &String,
+    theme_name: &str,
 ) -> Result<(), String> {
+    let base16_colors = &theme.colors;
     let file_conf_str = format!(
         "
-        # {}
+{}
 
 colors:
   primary:
@@ -111,7 +244,7 @@ colors:
     cyan: \"#{}\"
     white: \"#{}\"
 ",
-        theme_name,
+        metadata_comments(&theme.metadata, theme_name, "#"),
         base16_colors.get("background").unwrap(),
         base16_colors.get("foreground").unwrap(),
         base16_colors
@@ -136,9 +269,12 @@ colors:
         base16_colors.get("color15").unwrap(),
     );
 
-    let write_path = format!("{}/{}.yml", alacritty_path, theme_name.to_lowercase());
+    let write_path = format!(
+        "{}/{}",
+        alacritty_path,
+        output_file_name("alacritty", theme_name)
+    );
 
-    println!("{}", file_conf_str);
     let result = fs::write(write_path, file_conf_str.trim());
     match result {
         Err(_) => Err("Error writing file".to_string()),
@@ -147,13 +283,14 @@ colors:
 }
 
 pub fn create_kitty_theeme(
-    base16_colors: &Base16Colors,
+    theme: &Theme,
     kitty_path: &String,
-    theme_name: &String,
+    theme_name: &str,
 ) -> Result<(), String> {
+    let base16_colors = &theme.colors;
     let file_conf_str = format!(
         "
-## {}
+{}
 
     background #{}
     foreground #{}
@@ -176,7 +313,7 @@ pub fn create_kitty_theeme(
     color14 #{}
     color15 #{}
 ",
-        theme_name,
+        metadata_comments(&theme.metadata, theme_name, "##"),
         base16_colors.get("background").unwrap(),
         base16_colors.get("foreground").unwrap(),
         base16_colors.get("cursor").unwrap_or(&"FFFFFF".to_string()),
@@ -201,13 +338,918 @@ pub fn create_kitty_theeme(
         base16_colors.get("color15").unwrap(),
     );
 
-    let write_path = format!("{}/{}.conf", kitty_path, theme_name.to_lowercase());
-    println!("kitty_colors: from foot_theme {:#?}", write_path);
+    let write_path = format!("{}/{}", kitty_path, output_file_name("kitty", theme_name));
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_xresources_theme(
+    theme: &Theme,
+    xresources_path: &String,
+    theme_name: &str,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let file_conf_str = format!(
+        "
+{}
+
+*.background: #{}
+*.foreground: #{}
+*.cursorColor: #{}
+*.cursorColor2: #{}
+
+! black
+*.color0: #{}
+*.color8: #{}
+
+! red
+*.color1: #{}
+*.color9: #{}
+
+! green
+*.color2: #{}
+*.color10: #{}
+
+! yellow
+*.color3: #{}
+*.color11: #{}
+
+! blue
+*.color4: #{}
+*.color12: #{}
+
+! magenta
+*.color5: #{}
+*.color13: #{}
+
+! cyan
+*.color6: #{}
+*.color14: #{}
+
+! white
+*.color7: #{}
+*.color15: #{}
+",
+        metadata_comments(&theme.metadata, theme_name, "!"),
+        base16_colors.get("background").unwrap(),
+        base16_colors.get("foreground").unwrap(),
+        base16_colors.get("cursor").unwrap_or(&"FFFFFF".to_string()),
+        base16_colors
+            .get("cursor_fg")
+            .unwrap_or(&"000000".to_string()),
+        base16_colors.get("color0").unwrap(),
+        base16_colors.get("color8").unwrap(),
+        base16_colors.get("color1").unwrap(),
+        base16_colors.get("color9").unwrap(),
+        base16_colors.get("color2").unwrap(),
+        base16_colors.get("color10").unwrap(),
+        base16_colors.get("color3").unwrap(),
+        base16_colors.get("color11").unwrap(),
+        base16_colors.get("color4").unwrap(),
+        base16_colors.get("color12").unwrap(),
+        base16_colors.get("color5").unwrap(),
+        base16_colors.get("color13").unwrap(),
+        base16_colors.get("color6").unwrap(),
+        base16_colors.get("color14").unwrap(),
+        base16_colors.get("color7").unwrap(),
+        base16_colors.get("color15").unwrap(),
+    );
+
+    let write_path = format!(
+        "{}/{}",
+        xresources_path,
+        output_file_name("xresources", theme_name)
+    );
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_gnome_terminal_theme(
+    theme: &Theme,
+    gnome_terminal_path: &String,
+    theme_name: &str,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let palette = (0..16)
+        .map(|index| {
+            format!(
+                "'#{}'",
+                base16_colors.get(&format!("color{}", index)).unwrap()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    let highlight_colors = match base16_colors.get("selection_background") {
+        Some(selection_background) => format!(
+            "highlight-colors-set=true\nhighlight-background-color='#{}'\nhighlight-foreground-color='#{}'\n",
+            selection_background,
+            base16_colors
+                .get("selection_foreground")
+                .unwrap_or(base16_colors.get("foreground").unwrap()),
+        ),
+        None => String::new(),
+    };
+    let file_conf_str = format!(
+        "
+{}
+[/]
+visible-name='{}'
+use-theme-colors=false
+background-color='#{}'
+foreground-color='#{}'
+bold-color-same-as-fg=true
+cursor-colors-set=true
+cursor-background-color='#{}'
+cursor-foreground-color='#{}'
+{}palette=[{}]
+",
+        metadata_comments(&theme.metadata, theme_name, "#"),
+        display_name(&theme.metadata, theme_name)
+            .replace('\\', "\\\\")
+            .replace('\'', "\\'"),
+        base16_colors.get("background").unwrap(),
+        base16_colors.get("foreground").unwrap(),
+        base16_colors.get("cursor").unwrap_or(&"FFFFFF".to_string()),
+        base16_colors
+            .get("cursor_fg")
+            .unwrap_or(&"000000".to_string()),
+        highlight_colors,
+        palette,
+    );
+
+    if fs::create_dir_all(gnome_terminal_path).is_err() {
+        return Err("Error creating output folder".to_string());
+    }
+    let write_path = format!(
+        "{}/{}",
+        gnome_terminal_path,
+        output_file_name("gnome-terminal", theme_name)
+    );
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_konsole_theme(
+    theme: &Theme,
+    konsole_path: &String,
+    theme_name: &str,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    fn konsole_color(base16_colors: &Base16Colors, color_name: &str) -> String {
+        let color = Rgb::from_hex(base16_colors.get(color_name).unwrap()).unwrap();
+        format!("{},{},{}", color.r, color.g, color.b)
+    }
+
+    let mut file_conf_str = format!(
+        "{}\n\n",
+        metadata_comments(&theme.metadata, theme_name, "#")
+    );
+    for (section, color_name) in [("Background", "background"), ("Foreground", "foreground")] {
+        let color = konsole_color(base16_colors, color_name);
+        for variant in ["", "Intense", "Faint"] {
+            file_conf_str.push_str(&format!("[{}{}]\nColor={}\n\n", section, variant, color));
+        }
+    }
+    for number in 0..8 {
+        // without dim colors the faint variant falls back to the normal one
+        let faint_name = match base16_colors.contains_key(&format!("dim{}", number)) {
+            true => format!("dim{}", number),
+            false => format!("color{}", number),
+        };
+        file_conf_str.push_str(&format!(
+            "[Color{0}]\nColor={1}\n\n[Color{0}Intense]\nColor={2}\n\n[Color{0}Faint]\nColor={3}\n\n",
+            number,
+            konsole_color(base16_colors, &format!("color{}", number)),
+            konsole_color(base16_colors, &format!("color{}", number + 8)),
+            konsole_color(base16_colors, &faint_name),
+        ));
+    }
+    file_conf_str.push_str(&format!(
+        "[General]\nDescription={}\nOpacity=1\n",
+        display_name(&theme.metadata, theme_name)
+    ));
+
+    let write_path = format!(
+        "{}/{}",
+        konsole_path,
+        output_file_name("konsole", theme_name)
+    );
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+// derives the base00..base0F (and base10..base17 for base24) slots from a terminal palette,
+// the inverse of the base16-shell mapping used by the base16 parser
+fn base16_scheme_slots(base16_colors: &Base16Colors, base24: bool) -> Vec<(String, Rgb)> {
+    let color = |color_name: &str| Rgb::from_hex(base16_colors.get(color_name).unwrap()).unwrap();
+    let background = color("background");
+    let foreground = color("foreground");
+    let comment = color("color8");
+    let selection = base16_colors
+        .get("selection_background")
+        .and_then(|color_hex| Rgb::from_hex(color_hex))
+        .unwrap_or(background.mix(comment, 2.0 / 3.0));
+
+    let mut slots = vec![
+        background,
+        background.mix(comment, 1.0 / 3.0),
+        selection,
+        comment,
+        comment.mix(foreground, 0.5),
+        foreground,
+        foreground.mix(color("color15"), 0.5),
+        color("color15"),
+        color("color1"),
+        color("color1").mix(color("color3"), 0.5),
+        color("color3"),
+        color("color2"),
+        color("color6"),
+        color("color4"),
+        color("color5"),
+        color("color1").mix(background, 0.35),
+    ];
+    if base24 {
+        slots.extend([
+            background.mix(Rgb { r: 0, g: 0, b: 0 }, 0.25),
+            background.mix(Rgb { r: 0, g: 0, b: 0 }, 0.5),
+            color("color9"),
+            color("color11"),
+            color("color10"),
+            color("color14"),
+            color("color12"),
+            color("color13"),
+        ]);
+    }
+    slots
+        .into_iter()
+        .enumerate()
+        .map(|(number, color)| (format!("base{:02X}", number), color))
+        .collect()
+}
+
+fn create_base16_scheme(
+    theme: &Theme,
+    scheme_path: &str,
+    theme_name: &str,
+    base24: bool,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let system = if base24 { "base24" } else { "base16" };
+    let variant = match Rgb::from_hex(base16_colors.get("background").unwrap())
+        .unwrap()
+        .luminance()
+        > 0.5
+    {
+        true => "light",
+        false => "dark",
+    };
+    let palette = base16_scheme_slots(base16_colors, base24)
+        .iter()
+        .map(|(slot, color)| format!("  {}: \"#{}\"", slot, color.to_hex()))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let file_conf_str = format!(
+        "
+{}system: \"{}\"
+name: \"{}\"
+author: \"{}\"
+variant: \"{}\"
+palette:
+{}
+",
+        license_comments(&theme.metadata, "#"),
+        system,
+        display_name(&theme.metadata, theme_name).replace('"', "\\\""),
+        theme
+            .metadata
+            .author
+            .as_deref()
+            .unwrap_or("Ported with TTparser")
+            .replace('"', "\\\""),
+        variant,
+        palette,
+    );
+
+    if fs::create_dir_all(scheme_path).is_err() {
+        return Err("Error creating output folder".to_string());
+    }
+    let write_path = format!("{}/{}", scheme_path, output_file_name(system, theme_name));
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_base16_theme(
+    theme: &Theme,
+    base16_path: &str,
+    theme_name: &str,
+) -> Result<(), String> {
+    create_base16_scheme(theme, base16_path, theme_name, false)
+}
+
+pub fn create_base24_theme(
+    theme: &Theme,
+    base24_path: &str,
+    theme_name: &str,
+) -> Result<(), String> {
+    create_base16_scheme(theme, base24_path, theme_name, true)
+}
+
+pub fn create_gogh_theme(theme: &Theme, gogh_path: &str, theme_name: &str) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let ansi_names = [
+        "Black", "Red", "Green", "Yellow", "Blue", "Magenta", "Cyan", "White",
+    ];
+    let variant = match Rgb::from_hex(base16_colors.get("background").unwrap())
+        .unwrap()
+        .luminance()
+        > 0.5
+    {
+        true => "light",
+        false => "dark",
+    };
+    // gogh numbers the colors from 1, color_01 is color0
+    let colors = (0..16)
+        .map(|number| {
+            format!(
+                "color_{:02}: '#{}'    # {}{}",
+                number + 1,
+                base16_colors.get(&format!("color{}", number)).unwrap(),
+                if number < 8 { "" } else { "Bright " },
+                ansi_names[number % 8]
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let file_conf_str = format!(
+        "
+---
+{}name: '{}'
+author: '{}'
+variant: '{}'
+
+{}
+
+background: '#{}'
+foreground: '#{}'
+
+cursor: '#{}'
+",
+        license_comments(&theme.metadata, "#"),
+        display_name(&theme.metadata, theme_name).replace('\'', "''"),
+        theme
+            .metadata
+            .author
+            .as_deref()
+            .unwrap_or("Ported with TTparser")
+            .replace('\'', "''"),
+        variant,
+        colors,
+        base16_colors.get("background").unwrap(),
+        base16_colors.get("foreground").unwrap(),
+        base16_colors
+            .get("cursor")
+            .unwrap_or(base16_colors.get("foreground").unwrap()),
+    );
+
+    if fs::create_dir_all(gogh_path).is_err() {
+        return Err("Error creating output folder".to_string());
+    }
+    let write_path = format!("{}/{}", gogh_path, output_file_name("gogh", theme_name));
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_vscode_theme(
+    theme: &Theme,
+    vscode_path: &String,
+    theme_name: &str,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let ansi_names = [
+        "Black", "Red", "Green", "Yellow", "Blue", "Magenta", "Cyan", "White",
+    ];
+    let mut color_customizations: Vec<(String, &String)> = vec![
+        (
+            "terminal.background".to_string(),
+            base16_colors.get("background").unwrap(),
+        ),
+        (
+            "terminal.foreground".to_string(),
+            base16_colors.get("foreground").unwrap(),
+        ),
+    ];
+    for (number, name) in ansi_names.iter().enumerate() {
+        color_customizations.push((
+            format!("terminal.ansi{}", name),
+            base16_colors.get(&format!("color{}", number)).unwrap(),
+        ));
+        color_customizations.push((
+            format!("terminal.ansiBright{}", name),
+            base16_colors.get(&format!("color{}", number + 8)).unwrap(),
+        ));
+    }
+    for (key, color_name) in [
+        ("terminalCursor.foreground", "cursor"),
+        ("terminalCursor.background", "cursor_fg"),
+        ("terminal.selectionBackground", "selection_background"),
+        ("terminal.selectionForeground", "selection_foreground"),
+    ] {
+        if let Some(color_hex) = base16_colors.get(color_name) {
+            color_customizations.push((key.to_string(), color_hex));
+        }
+    }
+
+    let file_conf_str = format!(
+        "
+{}
+{{
+  \"workbench.colorCustomizations\": {{
+{}
+  }}
+}}
+",
+        metadata_comments(&theme.metadata, theme_name, "//"),
+        color_customizations
+            .iter()
+            .map(|(key, color_hex)| format!("    \"{}\": \"#{}\"", key, color_hex))
+            .collect::<Vec<String>>()
+            .join(",\n"),
+    );
+
+    if fs::create_dir_all(vscode_path).is_err() {
+        return Err("Error creating output folder".to_string());
+    }
+    let write_path = format!("{}/{}", vscode_path, output_file_name("vscode", theme_name));
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_neovim_theme(
+    theme: &Theme,
+    neovim_path: &String,
+    theme_name: &str,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let terminal_colors = (0..16)
+        .map(|number| {
+            format!(
+                "vim.g.terminal_color_{} = \"#{}\"",
+                number,
+                base16_colors.get(&format!("color{}", number)).unwrap()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let file_conf_str = format!(
+        "
+{}
+
+vim.g.colors_name = \"{}\"
+
+{}
+
+vim.api.nvim_set_hl(0, \"Normal\", {{ fg = \"#{}\", bg = \"#{}\" }})
+",
+        metadata_comments(&theme.metadata, theme_name, "--"),
+        theme_slug(theme_name),
+        terminal_colors,
+        base16_colors.get("foreground").unwrap(),
+        base16_colors.get("background").unwrap(),
+    );
+
+    let write_path = format!("{}/{}", neovim_path, output_file_name("neovim", theme_name));
+
+    let result = fs::write(write_path, file_conf_str.trim());
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_vim_theme(theme: &Theme, vim_path: &String, theme_name: &str) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let palette: Vec<&String> = (0..16)
+        .map(|number| base16_colors.get(&format!("color{}", number)).unwrap())
+        .collect();
+    let terminal_colors = palette
+        .iter()
+        .enumerate()
+        .map(|(number, color_hex)| format!("let g:terminal_color_{} = '#{}'", number, color_hex))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let file_conf_str = format!(
+        "
+{}
+
+let g:colors_name = '{}'
+
+\" Neovim
+{}
+
+\" Vim
+let g:terminal_ansi_colors = [{}]
+
+highlight Normal guifg=#{} guibg=#{}
+",
+        metadata_comments(&theme.metadata, theme_name, "\""),
+        theme_slug(theme_name),
+        terminal_colors,
+        palette
+            .iter()
+            .map(|color_hex| format!("'#{}'", color_hex))
+            .collect::<Vec<String>>()
+            .join(", "),
+        base16_colors.get("foreground").unwrap(),
+        base16_colors.get("background").unwrap(),
+    );
+
+    let write_path = format!("{}/{}", vim_path, output_file_name("vim", theme_name));
 
-    println!("{}", file_conf_str);
     let result = fs::write(write_path, file_conf_str.trim());
     match result {
         Err(_) => Err("Error writing file".to_string()),
         Ok(_) => Ok(()),
     }
 }
+
+// red, green and blue columns of the 16 ANSI colors, in the order the VT expects them
+fn vt_channels(base16_colors: &Base16Colors) -> [Vec<u8>; 3] {
+    let palette: Vec<Rgb> = (0..16)
+        .map(|number| {
+            Rgb::from_hex(base16_colors.get(&format!("color{}", number)).unwrap()).unwrap()
+        })
+        .collect();
+    [
+        palette.iter().map(|color| color.r).collect(),
+        palette.iter().map(|color| color.g).collect(),
+        palette.iter().map(|color| color.b).collect(),
+    ]
+}
+
+pub fn create_vt_theme(theme: &Theme, vt_path: &str, theme_name: &str) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    // setvtrgb takes three lines of 16 comma separated decimal values, no comments allowed
+    let file_conf_str = vt_channels(base16_colors)
+        .iter()
+        .map(|channel| {
+            channel
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    if fs::create_dir_all(vt_path).is_err() {
+        return Err("Error creating output folder".to_string());
+    }
+    let write_path = format!("{}/{}", vt_path, output_file_name("vt", theme_name));
+
+    let result = fs::write(write_path, file_conf_str + "\n");
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_vt_kernel_cmdline(
+    theme: &Theme,
+    vt_path: &str,
+    theme_name: &str,
+) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let file_conf_str = ["vt.default_red", "vt.default_grn", "vt.default_blu"]
+        .iter()
+        .zip(vt_channels(base16_colors))
+        .map(|(parameter, channel)| {
+            format!(
+                "{}={}",
+                parameter,
+                channel
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let write_path = format!("{}/{}", vt_path, output_file_name("vt-cmdline", theme_name));
+
+    let result = fs::write(write_path, file_conf_str + "\n");
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_svg_theme(theme: &Theme, svg_path: &str, theme_name: &str) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let write_path = format!("{}/{}", svg_path, output_file_name("svg", theme_name));
+
+    let result = fs::write(
+        write_path,
+        svg_preview(base16_colors, display_name(&theme.metadata, theme_name))?,
+    );
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_html_theme(theme: &Theme, html_path: &str, theme_name: &str) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let write_path = format!("{}/{}", html_path, output_file_name("html", theme_name));
+
+    let result = fs::write(
+        write_path,
+        html_preview(base16_colors, display_name(&theme.metadata, theme_name))?,
+    );
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn create_png_theme(theme: &Theme, png_path: &str, theme_name: &str) -> Result<(), String> {
+    let base16_colors = &theme.colors;
+    let write_path = format!("{}/{}", png_path, output_file_name("png", theme_name));
+
+    let result = fs::write(write_path, png_preview(base16_colors)?);
+    match result {
+        Err(_) => Err("Error writing file".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme_parser::{
+        base16_scheme_to_base16_colors, gnome_terminal_colors_to_base16_colors,
+        gogh_theme_to_base16_colors, vscode_colors_to_base16_colors,
+        xresources_colors_to_base16_colors,
+    };
+
+    const DCONF_DUMP: &str = r#"[:b1dcc9dd-5262-4d8d-a863-c897e6d979b9]
+visible-name='Tokyo Night \'Storm\' \\ dark'
+use-theme-colors=false
+background-color='#24283b'
+foreground-color='rgb(192,202,245)'
+cursor-colors-set=true
+cursor-background-color='#c0caf5'
+cursor-foreground-color='rgb(36,40,59)'
+highlight-colors-set=true
+highlight-background-color='#364a82'
+highlight-foreground-color='rgb(192,202,245)'
+palette=['#1d202f', 'rgb(247,118,142)', '#9ece6a', 'rgb(224,175,104)', '#7aa2f7', '#bb9af7', 'rgb(125,207,255)', '#a9b1d6', '#414868', '#f7768e', 'rgb(158,206,106)', '#e0af68', '#7aa2f7', 'rgb(187,154,247)', '#7dcfff', '#c0caf5']
+
+[:c2e6a8f0-0b4a-4c4b-9d0e-3f5e8c1f2a7d]
+visible-name='Other profile'
+background-color='#000000'
+foreground-color='#ffffff'
+palette=['#000000', '#800000', '#008000', '#808000', '#000080', '#800080', '#008080', '#c0c0c0', '#808080', '#ff0000', '#00ff00', '#ffff00', '#0000ff', '#ff00ff', '#00ffff', '#ffffff']
+"#;
+
+    #[test]
+    fn theme_slug_keeps_letters_of_every_script() {
+        assert_eq!(theme_slug("Tokyo Night / Storm"), "tokyo-night-storm");
+        assert_eq!(
+            theme_slug("Theme ported with TTParser."),
+            "theme-ported-with-ttparser"
+        );
+        assert_eq!(theme_slug("Rosé Pine Moon"), "rose-pine-moon");
+        assert_eq!(theme_slug("Ærlig Straße"), "aerlig-strasse");
+        assert_eq!(theme_slug("東京 Night"), "東京-night");
+        assert_eq!(theme_slug("../.."), "theme");
+        assert_eq!(output_file_name("base24", "Nord"), "nord.base24.yaml");
+        assert_eq!(output_file_name("foot", "Nord"), "nord");
+    }
+
+    #[test]
+    fn gnome_terminal_dconf_round_trip() {
+        let folder = std::env::temp_dir().join(format!("ttparser-dconf-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let dump_path = folder.join("dump.dconf");
+        fs::write(&dump_path, DCONF_DUMP).unwrap();
+
+        // only the first profile of the dump is read, whatever the color notation
+        let theme = gnome_terminal_colors_to_base16_colors(dump_path.to_str().unwrap());
+        assert_eq!(
+            theme.metadata.name.as_deref(),
+            Some("Tokyo Night 'Storm' \\ dark")
+        );
+        assert_eq!(theme.colors["background"], "24283b");
+        assert_eq!(theme.colors["foreground"], "c0caf5");
+        assert_eq!(theme.colors["cursor_fg"], "24283b");
+        assert_eq!(theme.colors["selection_background"], "364a82");
+        assert_eq!(theme.colors["selection_foreground"], "c0caf5");
+        assert_eq!(theme.colors["color1"], "f7768e");
+        assert_eq!(theme.colors["color13"], "bb9af7");
+        assert_eq!(theme.colors["color15"], "c0caf5");
+
+        let output_folder = folder.join("out").to_string_lossy().to_string();
+        create_gnome_terminal_theme(&theme, &output_folder, "tokyo-night-storm").unwrap();
+        let output_path = format!("{}/tokyo-night-storm.dconf", output_folder);
+        let output = fs::read_to_string(&output_path).unwrap();
+        assert!(output.contains(r#"visible-name='Tokyo Night \'Storm\' \\ dark'"#));
+        assert!(output.contains("highlight-colors-set=true"));
+        assert!(output.contains("highlight-background-color='#364a82'"));
+        assert!(output.contains("palette=['#1d202f', '#f7768e', "));
+
+        assert_eq!(gnome_terminal_colors_to_base16_colors(&output_path), theme);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    // a theme file of its own in the temp folder, for the readers that take a path
+    fn fixture(file_name: &str, contents: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("ttparser-{}-{}", std::process::id(), file_name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn xresources_defines_and_x11_colors() {
+        let path = fixture(
+            "defines.Xresources",
+            r#"! macros expanding to other macros
+#define base_bg #1a1b26
+#define bg base_bg
+#define red rgb:ffff/8000/0000
+#include "colors.h"
+*.background: bg
+URxvt*foreground: rgb:f/8/0
+*color1: red
+st.color9:  #F7768E
+*.cursorColor: bg
+*.color16: #ffffff
+"#,
+        );
+        let colors = xresources_colors_to_base16_colors(&path).colors;
+        assert_eq!(colors["background"], "1a1b26");
+        assert_eq!(colors["cursor"], "1a1b26");
+        // rgb: scales every component from its own number of hex digits
+        assert_eq!(colors["foreground"], "ff8800");
+        assert_eq!(colors["color1"], "ff8000");
+        assert_eq!(colors["color9"], "f7768e");
+        assert!(!colors.contains_key("color16"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    // every slot holds its own number, base0A is #0a0a0a
+    fn scheme_slots(slots: std::ops::Range<u8>, indent: &str) -> String {
+        slots
+            .map(|slot| {
+                format!(
+                    "{}base{:02X}: \"{:02x}{:02x}{:02x}\"\n",
+                    indent, slot, slot, slot, slot
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn base16_and_base24_terminal_slots() {
+        let base16_path = fixture(
+            "legacy.yaml",
+            &format!("scheme: \"Legacy\"\n{}", scheme_slots(0..16, "")),
+        );
+        let base24_path = fixture(
+            "base24.yaml",
+            &format!(
+                "system: \"base24\"\nname: \"Nested\"\npalette:\n{}",
+                scheme_slots(0..24, "  ").replace("base0D", "base0d")
+            ),
+        );
+        let slot_colors = |path: &str| {
+            let colors = base16_scheme_to_base16_colors(path).colors;
+            (0..16)
+                .map(|number| colors[&format!("color{}", number)][..2].to_string())
+                .collect::<Vec<String>>()
+        };
+        // base16 repeats the normal colors as brights, base24 has its own base12-base17
+        assert_eq!(
+            slot_colors(&base16_path),
+            [
+                "00", "08", "0b", "0a", "0d", "0e", "0c", "05", "03", "08", "0b", "0a", "0d", "0e",
+                "0c", "07"
+            ]
+        );
+        assert_eq!(
+            slot_colors(&base24_path),
+            [
+                "00", "08", "0b", "0a", "0d", "0e", "0c", "05", "03", "12", "14", "13", "16", "17",
+                "15", "07"
+            ]
+        );
+        let base24 = base16_scheme_to_base16_colors(&base24_path);
+        assert_eq!(base24.metadata.name.as_deref(), Some("Nested"));
+        assert_eq!(base24.colors["background"], "000000");
+        assert_eq!(base24.colors["foreground"], "050505");
+        assert_eq!(base24.colors["selection_background"], "020202");
+        fs::remove_file(&base16_path).unwrap();
+        fs::remove_file(&base24_path).unwrap();
+    }
+
+    #[test]
+    fn vscode_jsonc_and_fallback_colors() {
+        let path = fixture(
+            "jsonc.json",
+            r##"// Night Owl Light, with comments
+{
+    "name": "Night // Owl \"Light\"", /* a block
+    comment */
+    "type": "light",
+    "colors": {
+        "editor.background": "#fbfbfb", // no terminal.background
+        "terminal.foreground": "#403f53",
+        "terminal.ansiRed": "#de3d3bcc",
+        "terminal.ansiBlue": "#288",
+    },
+}
+"##,
+        );
+        let theme = vscode_colors_to_base16_colors(&path);
+        assert_eq!(
+            theme.metadata.name.as_deref(),
+            Some("Night // Owl \"Light\"")
+        );
+        assert_eq!(theme.metadata.variant.as_deref(), Some("light"));
+        assert_eq!(theme.colors["background"], "fbfbfb");
+        assert_eq!(theme.colors["foreground"], "403f53");
+        // the alpha channel is dropped, #rgb is widened
+        assert_eq!(theme.colors["color1"], "de3d3b");
+        assert_eq!(theme.colors["color4"], "228888");
+        // the ones left out are the light defaults of VS Code
+        assert_eq!(theme.colors["color2"], "00bc00");
+        assert_eq!(theme.colors["color15"], "a5a5a5");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn gogh_colors_are_numbered_from_zero() {
+        // color_01 holds #010101, color_16 #161616
+        let yaml_path = fixture(
+            "gogh.yml",
+            &format!(
+                "---\nname: 'Numbered'\nauthor: 'Gogh'\nvariant: 'Dark'\n\n{}{}",
+                (1..=16)
+                    .map(|number| format!("color_{0:02}: '#{0:02}{0:02}{0:02}'\n", number))
+                    .collect::<String>(),
+                "background: '#202020'\nforeground: \"#f0f0f0\"\ncursor: '#f0f0f0'\n"
+            ),
+        );
+        let json_path = fixture(
+            "gogh.json",
+            r##"{"name": "Numbered", "color_01": "#010101", "color_09": "#090909", "color_16": "#161616"}"##,
+        );
+        let yaml = gogh_theme_to_base16_colors(&yaml_path);
+        assert_eq!(yaml.metadata.name.as_deref(), Some("Numbered"));
+        assert_eq!(yaml.metadata.author.as_deref(), Some("Gogh"));
+        assert_eq!(yaml.colors["background"], "202020");
+        assert_eq!(yaml.colors["foreground"], "f0f0f0");
+        for number in 0..16 {
+            assert_eq!(
+                yaml.colors[&format!("color{}", number)],
+                format!("{:02}", number + 1).repeat(3)
+            );
+        }
+        let json = gogh_theme_to_base16_colors(&json_path).colors;
+        assert_eq!(json["color0"], "010101");
+        assert_eq!(json["color8"], "090909");
+        assert_eq!(json["color15"], "161616");
+        assert!(!json.contains_key("color16"));
+        fs::remove_file(&yaml_path).unwrap();
+        fs::remove_file(&json_path).unwrap();
+    }
+}
@@ -1,7 +1,11 @@
+use crate::color::Rgb;
+use crate::osc::osc_query_colors;
 use regex::Regex;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
 use toml::Table;
 
 #[derive(Debug)]
@@ -64,70 +68,130 @@ type ColorName = String;
 type ColorHex = String;
 pub type Base16Colors = HashMap<ColorName, ColorHex>;
 
-pub fn alacritty_colors_to_base16_colors(alacritty_colors_path: &str) -> Base16Colors {
+pub const THEME_METADATA: [&str; 5] = ["name", "author", "variant", "license", "upstream"];
+
+/// What a theme file says about itself, as plain text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeMetadata {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub variant: Option<String>,
+    pub license: Option<String>,
+    pub upstream: Option<String>,
+}
+
+impl ThemeMetadata {
+    fn field(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            "name" => Some(&mut self.name),
+            "author" => Some(&mut self.author),
+            "variant" => Some(&mut self.variant),
+            "license" => Some(&mut self.license),
+            "upstream" => Some(&mut self.upstream),
+            _ => None,
+        }
+    }
+
+    /// Sets `key`, one of `THEME_METADATA`, unless the theme already has it or `value` is empty.
+    pub fn insert_missing(&mut self, key: &str, value: &str) {
+        if let Some(field) = self.field(key) {
+            if field.is_none() && !value.is_empty() {
+                *field = Some(value.to_string());
+            }
+        }
+    }
+}
+
+/// A parsed theme: its colors, and its metadata kept apart from them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Theme {
+    pub colors: Base16Colors,
+    pub metadata: ThemeMetadata,
+}
+
+/// The colors every output needs, background, foreground and color0 to color15, that
+/// `base16_colors` does not have.
+pub fn missing_colors(base16_colors: &Base16Colors) -> Vec<String> {
+    ["background".to_string(), "foreground".to_string()]
+        .into_iter()
+        .chain((0..16).map(|number| format!("color{}", number)))
+        .filter(|color_name| !base16_colors.contains_key(color_name))
+        .collect()
+}
+
+// `## name: ...`, `! author: ...` comment lines, the kitty themes convention that
+// ttparser also writes, without replacing the metadata the format has on its own
+fn insert_comment_metadata(theme_file: &str, metadata: &mut ThemeMetadata) {
+    let comment = Regex::new(&format!(
+        r"(?mi)^\s*(?:#+|!|//|--|\x22)\s*({})\s*:\s*(.*?)\s*$",
+        THEME_METADATA.join("|")
+    ))
+    .unwrap();
+    for captures in comment.captures_iter(theme_file) {
+        metadata.insert_missing(&captures[1].to_lowercase(), &captures[2]);
+    }
+}
+
+pub fn alacritty_colors_to_base16_colors(alacritty_colors_path: &str) -> Theme {
     let mut is_yaml = alacritty_colors_path.contains("yaml");
     //  HACK: kinda hacky
     if !is_yaml {
         is_yaml = alacritty_colors_path.contains("yml");
     }
     let mut base16_colors: Base16Colors = HashMap::new();
-    let mut base16_color_number: i32 = 0;
+    let mut metadata = ThemeMetadata::default();
 
     if is_yaml {
         let file = File::open(alacritty_colors_path).expect("Error opening file");
         let reader = BufReader::new(file);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                // skipping comments
-                if line.starts_with("#") {
-                    continue;
-                }
-                println!("line {}", line);
-                let line_trimmed = line.trim();
-                if let Some(index) = line_trimmed.find(":") {
-                    let (color_name, color_hex) = line_trimmed.split_at(index);
-                    let color_hex = color_hex
-                        .trim()
-                        .trim_matches(|c: char| c == '"' || c == ':' || c.is_whitespace())
-                        .replace("#", "")
-                        .replace("'", "");
-                    println!("color_name: {}", color_name);
-
-                    match color_name {
-                        "background" => {
-                            base16_colors.insert("background".to_string(), color_hex.to_string());
-                        }
-                        "foreground" => {
-                            base16_colors.insert("foreground".to_string(), color_hex.to_string());
-                        }
-                        "cursor" => {
-                            base16_colors.insert("cursor".to_string(), color_hex.to_string());
+        for line in reader.lines().map_while(Result::ok) {
+            // skipping comments
+            if line.starts_with("#") {
+                continue;
+            }
+            let line_trimmed = line.trim();
+            if let Some(index) = line_trimmed.find(":") {
+                let (color_name, color_hex) = line_trimmed.split_at(index);
+                let color_hex = color_hex
+                    .trim()
+                    .trim_matches(|c: char| c == '"' || c == ':' || c.is_whitespace())
+                    .replace("#", "")
+                    .replace("'", "");
+
+                match color_name {
+                    "background" => {
+                        base16_colors.insert("background".to_string(), color_hex);
+                    }
+                    "foreground" => {
+                        base16_colors.insert("foreground".to_string(), color_hex);
+                    }
+                    "cursor" => {
+                        base16_colors.insert("cursor".to_string(), color_hex);
+                    }
+                    "text" => {
+                        base16_colors.insert("cursor_fg".to_string(), color_hex);
+                    }
+                    _ => {
+                        // skiping color fields
+                        if color_name.starts_with("color")
+                            || color_name.starts_with("primary")
+                            || color_name.starts_with("cursor")
+                            || color_name.starts_with("normal")
+                            || color_name.starts_with("bright")
+                        {
+                            continue;
                         }
-                        "text" => {
-                            base16_colors.insert("cursor_fg".to_string(), color_hex.to_string());
+                        if let Some(color) = AlacrittyColorsNormal::from_str(color_name) {
+                            let base16_color_number: u8 = color as u8;
+                            let color_hex = color_hex.replace("#", "");
+                            base16_colors
+                                .insert(format!("color{}", base16_color_number), color_hex);
                         }
-                        _ => {
-                            // skiping color fields
-                            if color_name.starts_with("color")
-                                || color_name.starts_with("primary")
-                                || color_name.starts_with("cursor")
-                                || color_name.starts_with("normal")
-                                || color_name.starts_with("bright")
-                            {
-                                continue;
-                            }
-                            if let Some(color) = AlacrittyColorsNormal::from_str(color_name) {
-                                let base16_color_number: u8 = color as u8;
-                                let color_hex = color_hex.replace("#", "");
-                                base16_colors
-                                    .insert(format!("color{}", base16_color_number), color_hex);
-                            }
-                            if let Some(color) = AlacrittyColorsBright::from_str(color_name) {
-                                let base16_color_number: u8 = color as u8;
-                                let color_hex = color_hex.replace("#", "");
-                                base16_colors
-                                    .insert(format!("color{}", base16_color_number), color_hex);
-                            }
+                        if let Some(color) = AlacrittyColorsBright::from_str(color_name) {
+                            let base16_color_number: u8 = color as u8;
+                            let color_hex = color_hex.replace("#", "");
+                            base16_colors
+                                .insert(format!("color{}", base16_color_number), color_hex);
                         }
                     }
                 }
@@ -170,13 +234,19 @@ pub fn alacritty_colors_to_base16_colors(alacritty_colors_path: &str) -> Base16C
             base16_colors.insert(key.to_string(), value.as_str().unwrap().replace("#", ""));
         }
     }
-    println!("base16_color: {:#?}", base16_colors);
-    return base16_colors;
+    if let Ok(alacritty_colors_file) = fs::read_to_string(alacritty_colors_path) {
+        insert_comment_metadata(&alacritty_colors_file, &mut metadata);
+    }
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
 }
 
-pub fn kitty_colors_to_base16_colors(kitty_colors_path: &String) -> Base16Colors {
+pub fn kitty_colors_to_base16_colors(kitty_colors_path: &str) -> Theme {
     let kitty_colors_file = fs::read_to_string(kitty_colors_path).unwrap();
     let mut base16_colors: Base16Colors = HashMap::new();
+    let mut metadata = ThemeMetadata::default();
 
     fn check_color(line: &str) -> bool {
         line.contains("color")
@@ -185,49 +255,724 @@ pub fn kitty_colors_to_base16_colors(kitty_colors_path: &String) -> Base16Colors
             || Regex::new(r"\bforeground\b").unwrap().is_match(line)
     }
 
-    for line in kitty_colors_file.trim().lines().into_iter() {
-        match check_color(line) {
-            true => {
-                // skipping comments
-                if line.starts_with("#") {
-                    continue;
-                };
+    for line in kitty_colors_file.trim().lines() {
+        if check_color(line) {
+            // skipping comments
+            if line.starts_with("#") {
+                continue;
+            };
 
-                let index = line.find("#").unwrap();
+            let index = line.find("#").unwrap();
 
-                let (color_name, color_hex) = line.split_at(index);
-                let color_hex = color_hex.trim().replace("#", "");
-                println!("color_hex: {}", color_hex);
+            let (color_name, color_hex) = line.split_at(index);
+            let color_hex = color_hex.trim().replace("#", "");
 
-                //  checking if the color is a reference for another color token
-                if check_color(color_hex.as_str()) {
-                    if line.contains("cursor_text_color") {
-                        base16_colors.insert(
-                            "cursor_fg".to_string(),
-                            base16_colors
-                                .get(&color_name.to_string())
-                                .unwrap()
-                                .to_string(),
-                        );
-                        continue;
-                    }
+            //  checking if the color is a reference for another color token
+            if check_color(color_hex.as_str()) {
+                if line.contains("cursor_text_color") {
                     base16_colors.insert(
-                        color_name.to_string(),
-                        base16_colors
-                            .get(&color_name.trim().to_string())
-                            .unwrap()
-                            .to_string(),
+                        "cursor_fg".to_string(),
+                        base16_colors.get(color_name).unwrap().to_string(),
                     );
                     continue;
                 }
-                if line.contains("cursor_text_color") {
-                    base16_colors.insert("cursor_fg".to_string(), color_hex);
+                base16_colors.insert(
+                    color_name.to_string(),
+                    base16_colors.get(color_name.trim()).unwrap().to_string(),
+                );
+                continue;
+            }
+            if line.contains("cursor_text_color") {
+                base16_colors.insert("cursor_fg".to_string(), color_hex);
+                continue;
+            }
+            base16_colors.insert(color_name.trim().to_string(), color_hex);
+        }
+    }
+    insert_comment_metadata(&kitty_colors_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+pub fn xresources_colors_to_base16_colors(xresources_path: &str) -> Theme {
+    let xresources_file = fs::read_to_string(xresources_path).expect("Error opening file");
+    let mut base16_colors: Base16Colors = HashMap::new();
+    let mut metadata = ThemeMetadata::default();
+    let mut defines: HashMap<String, String> = HashMap::new();
+    let identifier = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+
+    for line in xresources_file.lines() {
+        let line = line.trim();
+        // skipping comments and empty lines
+        if line.is_empty() || line.starts_with('!') {
+            continue;
+        }
+
+        if let Some(define) = line.strip_prefix("#define") {
+            let mut define = define.trim().splitn(2, char::is_whitespace);
+            if let (Some(name), Some(value)) = (define.next(), define.next()) {
+                defines.insert(name.to_string(), value.trim().to_string());
+            }
+            continue;
+        }
+        // other preprocessor directives (#include, #ifdef...) are ignored
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let Some((resource, value)) = line.split_once(':') else {
+            continue;
+        };
+        // `URxvt*cursorColor`, `*.color0`, `st.background`: only the last component matters
+        let resource = resource
+            .trim()
+            .rsplit(['.', '*'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let color_name = match resource.as_str() {
+            "background" | "foreground" => resource.clone(),
+            "cursorcolor" => "cursor".to_string(),
+            "cursorcolor2" => "cursor_fg".to_string(),
+            "highlightcolor" => "selection_background".to_string(),
+            "highlighttextcolor" => "selection_foreground".to_string(),
+            _ => match resource.strip_prefix("color").map(str::parse::<u8>) {
+                Some(Ok(number)) if number < 16 => resource.clone(),
+                _ => continue,
+            },
+        };
+
+        // macros can expand to other macros, so substitute until nothing changes
+        let mut value = value.trim().to_string();
+        for _ in 0..16 {
+            let substituted = identifier
+                .replace_all(&value, |captures: &regex::Captures| {
+                    let name = &captures[0];
+                    defines.get(name).cloned().unwrap_or(name.to_string())
+                })
+                .to_string();
+            if substituted == value {
+                break;
+            }
+            value = substituted;
+        }
+
+        match Rgb::from_x11(&value) {
+            Some(color) => {
+                base16_colors.insert(color_name, color.to_hex());
+            }
+            None => eprintln!("Skipping {}: unsupported color {}", color_name, value),
+        }
+    }
+    insert_comment_metadata(&xresources_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+pub fn gnome_terminal_colors_to_base16_colors(dconf_path: &str) -> Theme {
+    let dconf_file = fs::read_to_string(dconf_path).expect("Error opening file");
+    let mut base16_colors: Base16Colors = HashMap::new();
+    let mut metadata = ThemeMetadata::default();
+
+    fn parse_color(value: &str) -> Option<String> {
+        let value = value.trim().trim_matches('\'');
+        Rgb::from_hex(value)
+            .or_else(|| Rgb::from_decimal(value))
+            .map(Rgb::to_hex)
+    }
+
+    for line in dconf_file.lines() {
+        let line = line.trim();
+        // a dump of the whole profiles:/ directory has one section per profile, only the first is read
+        if line.starts_with('[') {
+            if !base16_colors.is_empty() {
+                break;
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let color_name = match key.trim() {
+            "background-color" => "background",
+            "foreground-color" => "foreground",
+            "cursor-background-color" => "cursor",
+            "cursor-foreground-color" => "cursor_fg",
+            "highlight-background-color" => "selection_background",
+            "highlight-foreground-color" => "selection_foreground",
+            "visible-name" => {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('\'')
+                    .and_then(|value| value.strip_suffix('\''))
+                    .unwrap_or(value);
+                // GVariant strings escape quotes and backslashes with a backslash
+                let mut name = String::new();
+                let mut characters = value.chars();
+                while let Some(character) = characters.next() {
+                    match character {
+                        '\\' => name.extend(characters.next()),
+                        _ => name.push(character),
+                    }
+                }
+                metadata.name = Some(name);
+                continue;
+            }
+            "palette" => {
+                let palette = value.trim().trim_start_matches('[').trim_end_matches(']');
+                for (index, color_hex) in palette.split("',").filter_map(parse_color).enumerate() {
+                    base16_colors.insert(format!("color{}", index), color_hex);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(color_hex) = parse_color(value) {
+            base16_colors.insert(color_name.to_string(), color_hex);
+        }
+    }
+    insert_comment_metadata(&dconf_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+pub fn konsole_colors_to_base16_colors(konsole_colors_path: &str) -> Theme {
+    let konsole_colors_file = fs::read_to_string(konsole_colors_path).expect("Error opening file");
+    let mut base16_colors: Base16Colors = HashMap::new();
+    let mut metadata = ThemeMetadata::default();
+    let mut section = String::new();
+
+    for line in konsole_colors_file.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.to_string();
+            continue;
+        }
+        if let (Some(("Description", name)), "General") = (line.split_once('='), section.as_str()) {
+            metadata.insert_missing("name", name.trim());
+            continue;
+        }
+        let Some(("Color", value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(color) = Rgb::from_decimal(value).or_else(|| Rgb::from_hex(value)) else {
+            eprintln!("Skipping [{}]: unsupported color {}", section, value);
+            continue;
+        };
+
+        // [Color3Intense] -> color11, [Color3Faint] -> dim3
+        let color_name = match section.as_str() {
+            "Background" => "background".to_string(),
+            "Foreground" => "foreground".to_string(),
+            _ => {
+                let Some(slot) = section.strip_prefix("Color") else {
+                    continue;
+                };
+                let (number, variant) = slot.split_at(
+                    slot.find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(slot.len()),
+                );
+                let Ok(number) = number.parse::<u8>() else {
                     continue;
+                };
+                match variant {
+                    "" => format!("color{}", number),
+                    "Intense" => format!("color{}", number + 8),
+                    "Faint" => format!("dim{}", number),
+                    _ => continue,
+                }
+            }
+        };
+        base16_colors.insert(color_name, color.to_hex());
+    }
+    insert_comment_metadata(&konsole_colors_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+// base16-shell mapping, base24 only differs on the bright colors
+const BASE16_TERMINAL_SLOTS: [&str; 16] = [
+    "base00", "base08", "base0B", "base0A", "base0D", "base0E", "base0C", "base05", "base03",
+    "base08", "base0B", "base0A", "base0D", "base0E", "base0C", "base07",
+];
+const BASE24_TERMINAL_SLOTS: [&str; 16] = [
+    "base00", "base08", "base0B", "base0A", "base0D", "base0E", "base0C", "base05", "base03",
+    "base12", "base14", "base13", "base16", "base17", "base15", "base07",
+];
+
+pub fn base16_scheme_to_base16_colors(scheme_path: &str) -> Theme {
+    let scheme_file = fs::read_to_string(scheme_path).expect("Error opening file");
+    let mut scheme: HashMap<String, String> = HashMap::new();
+    let mut metadata = ThemeMetadata::default();
+    let base_slot = Regex::new(r"^base[0-9A-Fa-f]{2}$").unwrap();
+    let quoted = Regex::new(r#"^["']([^"']*)["']"#).unwrap();
+
+    // works for both the legacy flat layout and the newer one nested under `palette:`
+    for line in scheme_file.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        // `scheme` is the name in the legacy layout
+        let metadata_key = match key {
+            "scheme" | "name" => Some("name"),
+            "author" | "variant" => Some(key),
+            _ => None,
+        };
+        if let Some(metadata_key) = metadata_key {
+            let value = match quoted.captures(value) {
+                Some(captures) => captures[1].to_string(),
+                None => value.to_string(),
+            };
+            metadata.insert_missing(metadata_key, &value);
+            continue;
+        }
+        if !base_slot.is_match(key) {
+            continue;
+        }
+        let value = match quoted.captures(value) {
+            Some(captures) => captures[1].to_string(),
+            None => value
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        match Rgb::from_hex(&value) {
+            Some(color) => {
+                // base0d and base0D are the same slot
+                let slot = format!("base{}", key[4..].to_uppercase());
+                scheme.insert(slot, color.to_hex());
+            }
+            None => eprintln!("Skipping {}: unsupported color {}", key, value),
+        }
+    }
+
+    let slots = match scheme.contains_key("base12") {
+        true => BASE24_TERMINAL_SLOTS,
+        false => BASE16_TERMINAL_SLOTS,
+    };
+    let mut base16_colors: Base16Colors = HashMap::new();
+    for (number, slot) in slots.iter().enumerate() {
+        if let Some(color_hex) = scheme.get(*slot) {
+            base16_colors.insert(format!("color{}", number), color_hex.to_string());
+        }
+    }
+    for (color_name, slot) in [
+        ("background", "base00"),
+        ("foreground", "base05"),
+        ("cursor", "base05"),
+        ("cursor_fg", "base00"),
+        ("selection_background", "base02"),
+        ("selection_foreground", "base05"),
+    ] {
+        if let Some(color_hex) = scheme.get(slot) {
+            base16_colors.insert(color_name.to_string(), color_hex.to_string());
+        }
+    }
+    insert_comment_metadata(&scheme_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+pub fn gogh_theme_to_base16_colors(gogh_theme_path: &str) -> Theme {
+    let gogh_theme_file = fs::read_to_string(gogh_theme_path).expect("Error opening file");
+    let mut gogh_theme: HashMap<String, String> = HashMap::new();
+
+    // the same keys in the YAML themes and the JSON ones
+    if gogh_theme_file.trim_start().starts_with('{') {
+        let gogh_json: serde_json::Value =
+            serde_json::from_str(&gogh_theme_file).expect("Error parsing JSON");
+        if let Some(keys) = gogh_json.as_object() {
+            for (key, value) in keys {
+                if let Some(value) = value.as_str() {
+                    gogh_theme.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+    } else {
+        let quoted = Regex::new(r#"^["']([^"']*)["']"#).unwrap();
+        for line in gogh_theme_file.lines() {
+            let Some((key, value)) = line.trim().split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            let value = match quoted.captures(value) {
+                Some(captures) => captures[1].to_string(),
+                None => value
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+            gogh_theme.insert(key.trim().to_string(), value);
+        }
+    }
+
+    // color_01 to color_16 are color0 to color15
+    let mut keys: Vec<(String, String)> = (0..16)
+        .map(|number| {
+            (
+                format!("color_{:02}", number + 1),
+                format!("color{}", number),
+            )
+        })
+        .collect();
+    for key in ["background", "foreground", "cursor"] {
+        keys.push((key.to_string(), key.to_string()));
+    }
+    let mut base16_colors: Base16Colors = HashMap::new();
+    for (key, color_name) in keys {
+        let Some(value) = gogh_theme.get(&key) else {
+            continue;
+        };
+        match Rgb::from_hex(value) {
+            Some(color) => {
+                base16_colors.insert(color_name, color.to_hex());
+            }
+            None => eprintln!("Skipping {}: unsupported color {}", key, value),
+        }
+    }
+    let mut metadata = ThemeMetadata::default();
+    for key in ["name", "author", "variant"] {
+        if let Some(value) = gogh_theme.get(key) {
+            metadata.insert_missing(key, value);
+        }
+    }
+    insert_comment_metadata(&gogh_theme_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+const VSCODE_ANSI_NAMES: [&str; 16] = [
+    "Black",
+    "Red",
+    "Green",
+    "Yellow",
+    "Blue",
+    "Magenta",
+    "Cyan",
+    "White",
+    "BrightBlack",
+    "BrightRed",
+    "BrightGreen",
+    "BrightYellow",
+    "BrightBlue",
+    "BrightMagenta",
+    "BrightCyan",
+    "BrightWhite",
+];
+
+// the terminal colors VS Code shows when a theme leaves them out, for dark and light themes
+const VSCODE_DARK_ANSI_COLORS: [&str; 16] = [
+    "000000", "cd3131", "0dbc79", "e5e510", "2472c8", "bc3fbc", "11a8cd", "e5e5e5", "666666",
+    "f14c4c", "23d18b", "f5f543", "3b8eea", "d670d6", "29b8db", "e5e5e5",
+];
+const VSCODE_LIGHT_ANSI_COLORS: [&str; 16] = [
+    "000000", "cd3131", "00bc00", "949800", "0451a5", "bc05bc", "0598bc", "555555", "666666",
+    "cd3131", "14ce14", "b5ba00", "0451a5", "bc05bc", "0598bc", "a5a5a5",
+];
+
+// a color of the theme as lowercase hex, without the alpha channel of #rgba and #rrggbbaa
+fn vscode_color(colors: &serde_json::Value, key: &str) -> Option<String> {
+    let value = colors.get(key)?.as_str()?.trim_start_matches('#');
+    let value = match value.len() {
+        4 | 8 => &value[..value.len() / 4 * 3],
+        _ => value,
+    };
+    match Rgb::from_hex(value) {
+        Some(color) => Some(color.to_hex()),
+        None => {
+            eprintln!("Skipping {}: unsupported color {}", key, value);
+            None
+        }
+    }
+}
+
+// VS Code reads JSON with comments and trailing commas, serde_json does not
+fn strip_jsonc(jsonc: &str) -> String {
+    let mut json = String::with_capacity(jsonc.len());
+    let mut chars = jsonc.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                json.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        json.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
                 }
-                base16_colors.insert(color_name.trim().to_string(), color_hex);
             }
-            false => println!("Nop."),
+            _ => json.push(c),
+        }
+    }
+    Regex::new(r",(\s*[}\]])")
+        .unwrap()
+        .replace_all(&json, "$1")
+        .to_string()
+}
+
+pub fn vscode_colors_to_base16_colors(vscode_theme_path: &str) -> Theme {
+    let vscode_theme_file = fs::read_to_string(vscode_theme_path).expect("Error opening file");
+    let vscode_theme: serde_json::Value =
+        serde_json::from_str(&strip_jsonc(&vscode_theme_file)).expect("Error parsing JSON");
+    let mut base16_colors: Base16Colors = HashMap::new();
+
+    // a color theme keeps them under `colors`, settings.json under `workbench.colorCustomizations`
+    let colors = match (
+        vscode_theme.get("colors"),
+        vscode_theme.get("workbench.colorCustomizations"),
+    ) {
+        (Some(colors), _) | (None, Some(colors)) => colors,
+        (None, None) => &vscode_theme,
+    };
+
+    let mut keys: Vec<(String, String)> = VSCODE_ANSI_NAMES
+        .iter()
+        .enumerate()
+        .map(|(number, name)| (format!("terminal.ansi{}", name), format!("color{}", number)))
+        .collect();
+    for (key, color_name) in [
+        ("terminal.background", "background"),
+        ("terminal.foreground", "foreground"),
+        ("terminalCursor.foreground", "cursor"),
+        ("terminalCursor.background", "cursor_fg"),
+        ("terminal.selectionBackground", "selection_background"),
+        ("terminal.selectionForeground", "selection_foreground"),
+    ] {
+        keys.push((key.to_string(), color_name.to_string()));
+    }
+
+    for (key, color_name) in keys {
+        if let Some(color) = vscode_color(colors, &key) {
+            base16_colors.insert(color_name, color);
+        }
+    }
+    // without terminal colors, the terminal takes the ones of the panel or the editor
+    for (color_name, fallback_keys) in [
+        ("background", ["panel.background", "editor.background"]),
+        ("foreground", ["panel.foreground", "editor.foreground"]),
+    ] {
+        if base16_colors.contains_key(color_name) {
+            continue;
+        }
+        if let Some(color) = fallback_keys
+            .iter()
+            .find_map(|key| vscode_color(colors, key))
+        {
+            base16_colors.insert(color_name.to_string(), color);
         }
     }
-    return base16_colors;
+    // a color theme has its name and `type`, dark, light, hc-dark or hc-light
+    let mut metadata = ThemeMetadata::default();
+    for key in ["name", "author"] {
+        if let Some(value) = vscode_theme.get(key).and_then(|value| value.as_str()) {
+            metadata.insert_missing(key, value);
+        }
+    }
+    if let Some(theme_type) = vscode_theme.get("type").and_then(|value| value.as_str()) {
+        let variant = match theme_type.ends_with("light") {
+            true => "light",
+            false => "dark",
+        };
+        metadata.insert_missing("variant", variant);
+    }
+    let default_colors = match metadata.variant.as_deref() {
+        Some("light") => VSCODE_LIGHT_ANSI_COLORS,
+        _ => VSCODE_DARK_ANSI_COLORS,
+    };
+    for (number, color) in default_colors.iter().enumerate() {
+        base16_colors
+            .entry(format!("color{}", number))
+            .or_insert(color.to_string());
+    }
+    insert_comment_metadata(&vscode_theme_file, &mut metadata);
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+pub fn pywal_colors_to_base16_colors(pywal_colors_path: &str) -> Theme {
+    let pywal_colors_file = fs::read_to_string(pywal_colors_path).expect("Error opening file");
+    let pywal_colors: serde_json::Value =
+        serde_json::from_str(&pywal_colors_file).expect("Error parsing JSON");
+    let mut base16_colors: Base16Colors = HashMap::new();
+
+    // `special.background` ... and `colors.color0` ..., as pywal and wallust write them
+    let mut keys: Vec<(&str, String, String)> = ["background", "foreground", "cursor"]
+        .iter()
+        .map(|name| ("special", name.to_string(), name.to_string()))
+        .collect();
+    for number in 0..16 {
+        let color_name = format!("color{}", number);
+        keys.push(("colors", color_name.clone(), color_name));
+    }
+
+    for (section, key, color_name) in keys {
+        let Some(value) = pywal_colors
+            .get(section)
+            .and_then(|colors| colors.get(&key))
+            .and_then(|value| value.as_str())
+        else {
+            continue;
+        };
+        match Rgb::from_hex(value) {
+            Some(color) => {
+                base16_colors.insert(color_name, color.to_hex());
+            }
+            None => eprintln!("Skipping {}.{}: unsupported color {}", section, key, value),
+        }
+    }
+    Theme {
+        colors: base16_colors,
+        ..Theme::default()
+    }
+}
+
+pub fn vim_colors_to_base16_colors(vim_colors_path: &str) -> Theme {
+    let vim_colors_file = fs::read_to_string(vim_colors_path).expect("Error opening file");
+    let mut base16_colors: Base16Colors = HashMap::new();
+    let hex = r#"["']#?([0-9A-Fa-f]{6})["']"#;
+
+    // `vim.g.terminal_color_0 = "#..."` and `let g:terminal_color_0 = '#...'`
+    let terminal_color = Regex::new(&format!(r"terminal_color_(\d+)\s*=\s*{}", hex)).unwrap();
+    for captures in terminal_color.captures_iter(&vim_colors_file) {
+        base16_colors.insert(format!("color{}", &captures[1]), captures[2].to_lowercase());
+    }
+    // Vim's own `let g:terminal_ansi_colors = ['#...', ...]`
+    let ansi_colors = Regex::new(r"terminal_ansi_colors\s*=\s*\[([^\]]*)\]").unwrap();
+    if let Some(captures) = ansi_colors.captures(&vim_colors_file) {
+        let color = Regex::new(hex).unwrap();
+        for (number, color_hex) in color.captures_iter(&captures[1]).enumerate() {
+            base16_colors
+                .entry(format!("color{}", number))
+                .or_insert(color_hex[1].to_lowercase());
+        }
+    }
+
+    // `vim.api.nvim_set_hl(0, "Normal", { fg = ..., bg = ... })`, through a local alias too,
+    // `Normal = { fg = ..., bg = ... }` in a table of groups and `highlight Normal guifg=... guibg=...`;
+    // a theme without any of them has no background
+    let normal_lua = Regex::new(
+        r#"(?:\w+\(\s*(?:0\s*,\s*)?["']Normal["']\s*,|[\s{,]\[?["']?Normal["']?\]?\s*=)\s*\{([^}]*)\}"#,
+    )
+    .unwrap();
+    let normal_vim = Regex::new(r"(?m)^\s*hi(?:ghlight)?!?\s+Normal\s+(.*)$").unwrap();
+    let normal_groups = normal_lua
+        .captures_iter(&vim_colors_file)
+        .chain(normal_vim.captures_iter(&vim_colors_file));
+    for captures in normal_groups {
+        for (color_name, attribute) in [
+            ("foreground", r"(?:\bfg\s*=\s*|guifg=)"),
+            ("background", r"(?:\bbg\s*=\s*|guibg=)"),
+        ] {
+            let attribute =
+                Regex::new(&format!(r#"{}["']?#?([0-9A-Fa-f]{{6}})"#, attribute)).unwrap();
+            if let Some(color_hex) = attribute.captures(&captures[1]) {
+                base16_colors.insert(color_name.to_string(), color_hex[1].to_lowercase());
+            }
+        }
+    }
+    let mut metadata = ThemeMetadata::default();
+    insert_comment_metadata(&vim_colors_file, &mut metadata);
+    // `vim.g.colors_name = "..."` and `let g:colors_name = '...'`, a file name more than
+    // a display name, so only when no `name:` comment gives one
+    let colors_name = Regex::new(r#"colors_name\s*=\s*["']([^"']+)["']"#).unwrap();
+    if let Some(captures) = colors_name.captures(&vim_colors_file) {
+        metadata.insert_missing("name", &captures[1]);
+    }
+    Theme {
+        colors: base16_colors,
+        metadata,
+    }
+}
+
+pub fn terminal_colors_to_base16_colors(tty_path: &str) -> Theme {
+    let mut tty = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(tty_path)
+        .expect("Error opening terminal");
+    let base16_colors = osc_query_colors(&mut tty, Duration::from_millis(1000));
+    if base16_colors.is_empty() {
+        panic!("{} did not answer the color queries", tty_path);
+    }
+    Theme {
+        colors: base16_colors,
+        ..Theme::default()
+    }
+}
+
+/// Guesses the `-i` name of a theme file from its extension and content,
+/// `None` when it is unreadable or looks like none of them.
+pub fn detect_theme_format(theme_path: &str) -> Option<&'static str> {
+    let theme_file = fs::read_to_string(theme_path).ok()?;
+    let extension = Path::new(theme_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let kitty_line =
+        Regex::new(r"(?m)^\s*(color\d+|background|foreground)\s+#?[0-9A-Fa-f]{6}\b").unwrap();
+    // `*.color0:`, `*background:`, or with a class, `URxvt*background:` and `st.foreground:`
+    let xresources_line =
+        Regex::new(r"(?m)^\s*\w*[*.][\w.*]*(color\d+|background|foreground)\s*:").unwrap();
+
+    let format = match extension.as_str() {
+        "json" | "jsonc" if theme_file.contains("\"special\"") => "pywal",
+        "json" | "jsonc" if theme_file.contains("\"color_01\"") => "gogh",
+        "json" | "jsonc" => "vscode",
+        "yml" | "yaml" if theme_file.contains("color_01") => "gogh",
+        "yml" | "yaml"
+            if Regex::new(r"(?m)^\s*base0[0-9A-Fa-f]\s*:")
+                .unwrap()
+                .is_match(&theme_file) =>
+        {
+            "base16"
+        }
+        "yml" | "yaml" | "toml" => "alacritty",
+        "colorscheme" => "konsole",
+        "lua" | "vim" => "vim",
+        _ if theme_file.contains("palette=[") => "gnome-terminal",
+        _ if xresources_line.is_match(&theme_file) => "xresources",
+        _ if kitty_line.is_match(&theme_file) => "kitty",
+        _ => return None,
+    };
+    Some(format)
 }
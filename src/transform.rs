@@ -0,0 +1,317 @@
+use crate::color::Rgb;
+use crate::contrast::{blends_into_background, contrast_ratio};
+use crate::theme_parser::Base16Colors;
+
+/// Moves the OKLCH lightness of `color` away from `background`, as little as possible,
+/// until their contrast ratio reaches `target`. Hue is kept, chroma only drops when the
+/// new lightness can't hold it in sRGB.
+fn nudge_lightness(color: Rgb, background: Rgb, target: f64) -> Rgb {
+    let oklch = color.to_oklch();
+    let towards_light = background.luminance() < 0.5;
+    let (mut near, mut far) = match towards_light {
+        true => (oklch.l, 1.0),
+        false => (oklch.l, 0.0),
+    };
+    let with_lightness = |l: f64| {
+        let mut nudged = oklch;
+        nudged.l = l;
+        nudged.to_rgb()
+    };
+    // best effort when even white/black is not enough
+    if contrast_ratio(with_lightness(far), background) < target {
+        return with_lightness(far);
+    }
+    for _ in 0..24 {
+        let middle = (near + far) / 2.0;
+        match contrast_ratio(with_lightness(middle), background) >= target {
+            true => far = middle,
+            false => near = middle,
+        }
+    }
+    with_lightness(far)
+}
+
+/// Fixes the foreground (held to at least WCAG AA, 4.5) and the ANSI colors
+/// (held to `target`) whose contrast against the background is too low.
+/// Returns a line describing every change.
+pub fn fix_contrast(base16_colors: &mut Base16Colors, target: f64) -> Vec<String> {
+    let background = Rgb::from_hex(base16_colors.get("background").unwrap()).unwrap();
+    let mut color_names = vec![("foreground".to_string(), target.max(4.5))];
+    for number in 0..16 {
+        let color_name = format!("color{}", number);
+        if !blends_into_background(&color_name, background) {
+            color_names.push((color_name, target));
+        }
+    }
+
+    let mut changes: Vec<String> = Vec::new();
+    for (color_name, target) in color_names {
+        let Some(color) = base16_colors
+            .get(&color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+        else {
+            continue;
+        };
+        let ratio = contrast_ratio(color, background);
+        if ratio >= target {
+            continue;
+        }
+        let fixed = nudge_lightness(color, background, target);
+        changes.push(format!(
+            "{}: #{} -> #{} ({:.2} -> {:.2}, target {:.2})",
+            color_name,
+            color.to_hex(),
+            fixed.to_hex(),
+            ratio,
+            contrast_ratio(fixed, background),
+            target
+        ));
+        base16_colors.insert(color_name, fixed.to_hex());
+    }
+    changes
+}
+
+// backgrounds sit near the ends of the lightness range, so they are mirrored around
+// those ends rather than around the middle: 0.2 <-> 0.95, 0.1 <-> 0.975
+fn opposite_background_lightness(l: f64, to_light: bool) -> f64 {
+    match to_light {
+        true => 1.0 - l / 4.0,
+        false => (1.0 - l) * 4.0,
+    }
+    .clamp(0.0, 1.0)
+}
+
+/// Derives the light variant of a dark theme, or the dark one of a light theme.
+/// Background-like roles swap ends, foreground-like roles mirror their lightness and
+/// the ANSI colors keep their hue, only moving as much as they need to stay legible on
+/// the new background. Black and white keep their meaning, as they do in light themes.
+pub fn opposite_variant(base16_colors: &Base16Colors) -> Base16Colors {
+    let mut variant = base16_colors.clone();
+    let color = |color_name: &str| {
+        base16_colors
+            .get(color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+    };
+    let to_light = color("background").unwrap().luminance() < 0.5;
+
+    for color_name in ["background", "cursor_fg", "selection_background"] {
+        if let Some(original) = color(color_name) {
+            let mut oklch = original.to_oklch();
+            oklch.l = opposite_background_lightness(oklch.l, to_light);
+            variant.insert(color_name.to_string(), oklch.to_rgb().to_hex());
+        }
+    }
+    let background = Rgb::from_hex(variant.get("background").unwrap()).unwrap();
+
+    for (color_name, pair_background) in [
+        ("foreground", "background"),
+        ("cursor", "cursor_fg"),
+        ("selection_foreground", "selection_background"),
+    ] {
+        let (Some(original), Some(pair_background)) = (
+            color(color_name),
+            variant
+                .get(pair_background)
+                .and_then(|color_hex| Rgb::from_hex(color_hex)),
+        ) else {
+            continue;
+        };
+        let mut oklch = original.to_oklch();
+        oklch.l = 1.0 - oklch.l;
+        let mirrored = oklch.to_rgb();
+        let legible = match contrast_ratio(mirrored, pair_background) < 4.5 {
+            true => nudge_lightness(mirrored, pair_background, 4.5),
+            false => mirrored,
+        };
+        variant.insert(color_name.to_string(), legible.to_hex());
+    }
+
+    let palette_names = (0..16)
+        .map(|number| format!("color{}", number))
+        .chain((0..8).map(|number| format!("dim{}", number)));
+    for color_name in palette_names {
+        if ["color0", "color7", "color15"].contains(&color_name.as_str())
+            || blends_into_background(&color_name, background)
+        {
+            continue;
+        }
+        if let Some(original) = color(&color_name) {
+            if contrast_ratio(original, background) < 3.0 {
+                let legible = nudge_lightness(original, background, 3.0);
+                variant.insert(color_name, legible.to_hex());
+            }
+        }
+    }
+    variant
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ColorTransform {
+    /// Adds to the OKLCH lightness (0.0 to 1.0).
+    Lighten(f64),
+    /// Subtracts from the OKLCH lightness (0.0 to 1.0).
+    Darken(f64),
+    /// Scales the chroma by `1 + amount`, negative amounts desaturate.
+    Saturate(f64),
+    /// Rotates the hue by some degrees.
+    HueRotate(f64),
+    /// Shifts towards orange, or towards blue for negative amounts (0.0 to 1.0).
+    Warmer(f64),
+    Grayscale,
+}
+
+impl ColorTransform {
+    pub fn apply(&self, color: Rgb) -> Rgb {
+        let mut oklch = color.to_oklch();
+        match *self {
+            ColorTransform::Lighten(amount) => oklch.l += amount,
+            ColorTransform::Darken(amount) => oklch.l -= amount,
+            ColorTransform::Saturate(amount) => oklch.c = (oklch.c * (1.0 + amount)).max(0.0),
+            ColorTransform::HueRotate(degrees) => oklch.h = (oklch.h + degrees).rem_euclid(360.0),
+            ColorTransform::Warmer(amount) => {
+                // moves along the a (green-red) and b (blue-yellow) axes of OKLab
+                let a = oklch.c * oklch.h.to_radians().cos() + amount * 0.03;
+                let b = oklch.c * oklch.h.to_radians().sin() + amount * 0.1;
+                oklch.c = (a * a + b * b).sqrt();
+                oklch.h = b.atan2(a).to_degrees().rem_euclid(360.0);
+            }
+            ColorTransform::Grayscale => oklch.c = 0.0,
+        }
+        oklch.to_rgb()
+    }
+}
+
+/// The color names a role covers: `palette`, `background`, `foreground`,
+/// `cursor`, `selection` or `all` of them.
+pub fn role_color_names(role: &str) -> Vec<String> {
+    match role {
+        "palette" => (0..16)
+            .map(|number| format!("color{}", number))
+            .chain((0..8).map(|number| format!("dim{}", number)))
+            .collect(),
+        "background" | "foreground" => vec![role.to_string()],
+        "cursor" => vec!["cursor".to_string(), "cursor_fg".to_string()],
+        "selection" => vec![
+            "selection_background".to_string(),
+            "selection_foreground".to_string(),
+        ],
+        "all" => ["palette", "background", "foreground", "cursor", "selection"]
+            .iter()
+            .flat_map(|role| role_color_names(role))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Runs the transforms, in order, on the colors of the given roles, once per color
+/// even when roles overlap (`palette,all`).
+pub fn transform_colors(
+    base16_colors: &mut Base16Colors,
+    transforms: &[ColorTransform],
+    roles: &[String],
+) {
+    let mut color_names: Vec<String> = Vec::new();
+    for color_name in roles.iter().flat_map(|role| role_color_names(role)) {
+        if !color_names.contains(&color_name) {
+            color_names.push(color_name);
+        }
+    }
+    for color_name in color_names {
+        let Some(mut color) = base16_colors
+            .get(&color_name)
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+        else {
+            continue;
+        };
+        for transform in transforms {
+            color = transform.apply(color);
+        }
+        base16_colors.insert(color_name, color.to_hex());
+    }
+}
+
+/// Which bright colors `generate_brights` derives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrightsMode {
+    /// Only the ones the theme does not have.
+    Missing,
+    /// The missing ones and the ones identical to their normal color.
+    Identical,
+    /// Every one of them.
+    Always,
+}
+
+/// Derives `color8`–`color15` from `color0`–`color7`, `strength` higher in OKLCH
+/// lightness, the ones `mode` picks. Returns a line per generated color.
+pub fn generate_brights(
+    base16_colors: &mut Base16Colors,
+    strength: f64,
+    mode: BrightsMode,
+) -> Vec<String> {
+    let mut changes: Vec<String> = Vec::new();
+    for number in 0..8 {
+        let Some(normal) = base16_colors
+            .get(&format!("color{}", number))
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+        else {
+            continue;
+        };
+        let bright_name = format!("color{}", number + 8);
+        let generate = match (mode, base16_colors.get(&bright_name)) {
+            (BrightsMode::Always, _) | (_, None) => true,
+            (BrightsMode::Identical, Some(bright)) => Rgb::from_hex(bright) == Some(normal),
+            (BrightsMode::Missing, Some(_)) => false,
+        };
+        if generate {
+            let mut bright = normal.to_oklch();
+            bright.l += strength;
+            let bright = bright.to_rgb();
+            changes.push(format!(
+                "{} #{} from color{}",
+                bright_name,
+                bright.to_hex(),
+                number
+            ));
+            base16_colors.insert(bright_name, bright.to_hex());
+        }
+    }
+    changes
+}
+
+/// Derives `dim0`–`dim7` from `color0`–`color7`, `strength` closer to the background
+/// in OKLCH lightness, all of them with `always`, otherwise only the missing ones.
+/// Returns a line per generated color.
+pub fn generate_dims(base16_colors: &mut Base16Colors, strength: f64, always: bool) -> Vec<String> {
+    let Some(background) = base16_colors
+        .get("background")
+        .and_then(|color_hex| Rgb::from_hex(color_hex))
+        .map(|background| background.to_oklch().l)
+    else {
+        return Vec::new();
+    };
+    let mut changes: Vec<String> = Vec::new();
+    for number in 0..8 {
+        let dim_name = format!("dim{}", number);
+        if !always && base16_colors.contains_key(&dim_name) {
+            continue;
+        }
+        let Some(normal) = base16_colors
+            .get(&format!("color{}", number))
+            .and_then(|color_hex| Rgb::from_hex(color_hex))
+        else {
+            continue;
+        };
+        // never past the lightness of the background
+        let mut dim = normal.to_oklch();
+        dim.l += (background - dim.l).signum() * strength.min((background - dim.l).abs());
+        let dim = dim.to_rgb();
+        changes.push(format!(
+            "{} #{} from color{}",
+            dim_name,
+            dim.to_hex(),
+            number
+        ));
+        base16_colors.insert(dim_name, dim.to_hex());
+    }
+    changes
+}